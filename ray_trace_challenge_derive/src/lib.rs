@@ -13,9 +13,8 @@ fn impl_groupable_macro(ast: &syn::DeriveInput) -> TokenStream {
     let gen = quote! {
 
         impl<'a> Groupable<'a> for #name<'a> {
-            fn set_parent(&mut self, parent: &Rc<RefCell<Group<'a>>>) {
-                let parent = Rc::clone(parent);
-                self.parent = Some(parent);
+            fn set_parent<P: Into<Parent<'a>>>(&mut self, parent: P) {
+                self.parent = Some(parent.into());
             }
         }
     };
@@ -0,0 +1,280 @@
+use crate::matrix4::Matrix4;
+use crate::ray::Ray;
+use crate::vec3::TypedVec;
+
+/// An empty box has its minimum above its maximum.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct BoundingBox {
+    pub min: TypedVec,
+    pub max: TypedVec,
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl BoundingBox {
+    pub fn new(min: TypedVec, max: TypedVec) -> Self {
+        Self { min, max }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            min: TypedVec::point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: TypedVec::point(-f64::INFINITY, -f64::INFINITY, -f64::INFINITY),
+        }
+    }
+
+    pub fn infinite() -> Self {
+        Self {
+            min: TypedVec::point(-f64::INFINITY, -f64::INFINITY, -f64::INFINITY),
+            max: TypedVec::point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn add_point(&mut self, p: TypedVec) {
+        self.min = TypedVec::point(
+            self.min.x.min(p.x),
+            self.min.y.min(p.y),
+            self.min.z.min(p.z),
+        );
+        self.max = TypedVec::point(
+            self.max.x.max(p.x),
+            self.max.y.max(p.y),
+            self.max.z.max(p.z),
+        );
+    }
+
+    pub fn merge(&mut self, other: &BoundingBox) {
+        if other.is_empty() {
+            return;
+        }
+        self.add_point(other.min);
+        self.add_point(other.max);
+    }
+
+    pub fn contains_point(&self, p: TypedVec) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x)
+            && (self.min.y..=self.max.y).contains(&p.y)
+            && (self.min.z..=self.max.z).contains(&p.z)
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    /// Axes an infinite extent gets smeared across come out infinite.
+    pub fn transform(&self, transform: &Matrix4) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+        let corners = [
+            TypedVec::point(self.min.x, self.min.y, self.min.z),
+            TypedVec::point(self.min.x, self.min.y, self.max.z),
+            TypedVec::point(self.min.x, self.max.y, self.min.z),
+            TypedVec::point(self.min.x, self.max.y, self.max.z),
+            TypedVec::point(self.max.x, self.min.y, self.min.z),
+            TypedVec::point(self.max.x, self.min.y, self.max.z),
+            TypedVec::point(self.max.x, self.max.y, self.min.z),
+            TypedVec::point(self.max.x, self.max.y, self.max.z),
+        ];
+        let mut bounds = BoundingBox::empty();
+        for corner in corners {
+            let p = transform_point(transform, corner);
+            // Opposite infinities cancel into NaN; all that tells us is that
+            // the axis is unbounded in both directions.
+            if p.iter().any(|c| c.is_nan()) {
+                let unbounded = |c: f64, inf: f64| if c.is_nan() { inf } else { c };
+                bounds.add_point(TypedVec::point(
+                    unbounded(p[0], f64::INFINITY),
+                    unbounded(p[1], f64::INFINITY),
+                    unbounded(p[2], f64::INFINITY),
+                ));
+                bounds.add_point(TypedVec::point(
+                    unbounded(p[0], -f64::INFINITY),
+                    unbounded(p[1], -f64::INFINITY),
+                    unbounded(p[2], -f64::INFINITY),
+                ));
+            } else {
+                bounds.add_point(TypedVec::point(p[0], p[1], p[2]));
+            }
+        }
+        bounds
+    }
+
+    pub fn intersects(&self, ray: Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x, self.min.x, self.max.x);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y, self.min.y, self.max.y);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z, self.min.z, self.max.z);
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        tmin <= tmax
+    }
+}
+
+// Like `Matrix4 * TypedVec`, but zero entries are skipped so that an infinite
+// coordinate on an axis the matrix ignores doesn't turn into NaN.
+fn transform_point(m: &Matrix4, p: TypedVec) -> [f64; 3] {
+    let coords = [p.x, p.y, p.z, p.w];
+    let mut out = [0f64; 3];
+    for (row, o) in out.iter_mut().enumerate() {
+        *o = (0..4)
+            .map(|col| (m.get(row, col).unwrap(), coords[col]))
+            .filter(|(a, _)| *a != 0f64)
+            .map(|(a, b)| a * b)
+            .sum();
+    }
+    out
+}
+
+fn check_axis(origin: f64, direction: f64, min: f64, max: f64) -> (f64, f64) {
+    let tmin = (min - origin) / direction;
+    let tmax = (max - origin) / direction;
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bounds::BoundingBox;
+    use crate::matrix::Axis;
+    use crate::matrix4::Matrix4;
+    use crate::ray::Ray;
+    use crate::vec3::TypedVec;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_empty() {
+        let b = BoundingBox::empty();
+        assert!(b.is_empty());
+        assert!(!b.contains_point(TypedVec::point(0f64, 0f64, 0f64)));
+    }
+
+    #[test]
+    fn test_add_points() {
+        let mut b = BoundingBox::empty();
+        b.add_point(TypedVec::point(-5f64, 2f64, 0f64));
+        b.add_point(TypedVec::point(7f64, 0f64, -3f64));
+        assert_eq!(b.min, TypedVec::point(-5f64, 0f64, -3f64));
+        assert_eq!(b.max, TypedVec::point(7f64, 2f64, 0f64));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut b1 = BoundingBox::new(
+            TypedVec::point(-5f64, -2f64, 0f64),
+            TypedVec::point(7f64, 4f64, 4f64),
+        );
+        let b2 = BoundingBox::new(
+            TypedVec::point(8f64, -7f64, -2f64),
+            TypedVec::point(14f64, 2f64, 8f64),
+        );
+        b1.merge(&b2);
+        assert_eq!(b1.min, TypedVec::point(-5f64, -7f64, -2f64));
+        assert_eq!(b1.max, TypedVec::point(14f64, 4f64, 8f64));
+        b1.merge(&BoundingBox::empty());
+        assert_eq!(b1.max, TypedVec::point(14f64, 4f64, 8f64));
+    }
+
+    #[test]
+    fn test_contains() {
+        let b = BoundingBox::new(
+            TypedVec::point(5f64, -2f64, 0f64),
+            TypedVec::point(11f64, 4f64, 7f64),
+        );
+        let examples = vec![
+            ("min", TypedVec::point(5f64, -2f64, 0f64), true),
+            ("max", TypedVec::point(11f64, 4f64, 7f64), true),
+            ("middle", TypedVec::point(8f64, 1f64, 3f64), true),
+            ("below x", TypedVec::point(3f64, 0f64, 3f64), false),
+            ("above y", TypedVec::point(8f64, 6f64, 1f64), false),
+            ("below z", TypedVec::point(8f64, 1f64, -1f64), false),
+        ];
+        for (name, p, expected) in examples {
+            dbg!(&name);
+            assert_eq!(b.contains_point(p), expected);
+        }
+        let inner = BoundingBox::new(
+            TypedVec::point(6f64, -1f64, 1f64),
+            TypedVec::point(10f64, 3f64, 6f64),
+        );
+        assert!(b.contains_box(&inner));
+        assert!(!inner.contains_box(&b));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn test_transform() {
+        let b = BoundingBox::new(
+            TypedVec::point(-1f64, -1f64, -1f64),
+            TypedVec::point(1f64, 1f64, 1f64),
+        );
+        let m = Matrix4::rotation(Axis::X, PI / 4f64) * Matrix4::rotation(Axis::Y, PI / 4f64);
+        let b = b.transform(&m);
+        assert_eq!(
+            b.min.round(10000f64),
+            TypedVec::point(-1.4142, -1.7071, -1.7071)
+        );
+        assert_eq!(
+            b.max.round(10000f64),
+            TypedVec::point(1.4142, 1.7071, 1.7071)
+        );
+    }
+
+    #[test]
+    fn test_transform_infinite() {
+        let plane = BoundingBox::new(
+            TypedVec::point(-f64::INFINITY, 0f64, -f64::INFINITY),
+            TypedVec::point(f64::INFINITY, 0f64, f64::INFINITY),
+        );
+        let b = plane.transform(&Matrix4::translation(0f64, 2f64, 0f64));
+        assert_eq!(b.min.y, 2f64);
+        assert_eq!(b.max.y, 2f64);
+        assert_eq!(b.min.x, -f64::INFINITY);
+
+        let b = plane.transform(&Matrix4::rotation(Axis::Z, PI / 2f64));
+        assert_eq!(b, BoundingBox::infinite());
+    }
+
+    #[test]
+    fn test_intersects() {
+        let b = BoundingBox::new(
+            TypedVec::point(5f64, -2f64, 0f64),
+            TypedVec::point(11f64, 4f64, 7f64),
+        );
+        let examples = vec![
+            ("+x", (15f64, 1f64, 2f64), (-1f64, 0f64, 0f64), true),
+            ("-x", (-5f64, -1f64, 4f64), (1f64, 0f64, 0f64), true),
+            ("+y", (7f64, 6f64, 5f64), (0f64, -1f64, 0f64), true),
+            ("-z", (9f64, 0f64, -5f64), (0f64, 0f64, 1f64), true),
+            ("inside", (8f64, 1f64, 3.5), (0f64, 0f64, 1f64), true),
+            ("miss x", (9f64, -1f64, -8f64), (2f64, 4f64, 6f64), false),
+            ("miss y", (8f64, 3f64, -4f64), (6f64, 2f64, 4f64), false),
+            ("miss z", (12f64, 5f64, 4f64), (-1f64, 0f64, 0f64), false),
+        ];
+        for (name, o, d, expected) in examples {
+            dbg!(&name);
+            let r = Ray::new(
+                TypedVec::point(o.0, o.1, o.2),
+                TypedVec::vector(d.0, d.1, d.2).normalize(),
+            );
+            assert_eq!(b.intersects(r), expected);
+        }
+        assert!(!BoundingBox::empty().intersects(Ray::new(
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        )));
+    }
+}
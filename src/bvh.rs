@@ -0,0 +1,255 @@
+use crate::bounds::BoundingBox;
+use crate::hittable::Hittable;
+use crate::intersection::Intersection;
+use crate::ray::Ray;
+use crate::vec3::TypedVec;
+
+const LEAF_SIZE: usize = 4;
+
+/// Split at the median centroid along the widest axis. Objects with infinite
+/// bounds sit outside the tree and are tested against every ray.
+#[derive(Clone, Debug, Default, PartialOrd, PartialEq)]
+pub struct Bvh<'a> {
+    root: Option<Node<'a>>,
+    unbounded: Vec<&'a dyn Hittable>,
+    stats: BvhStats,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialOrd, PartialEq)]
+pub struct BvhStats {
+    pub nodes: usize,
+    pub leaves: usize,
+    /// A lone leaf has depth 1 and an empty tree 0.
+    pub depth: usize,
+    pub unbounded: usize,
+}
+
+#[derive(Clone, Debug, PartialOrd, PartialEq)]
+enum Node<'a> {
+    Leaf {
+        bounds: BoundingBox,
+        objects: Vec<&'a dyn Hittable>,
+    },
+    Branch {
+        bounds: BoundingBox,
+        left: Box<Node<'a>>,
+        right: Box<Node<'a>>,
+    },
+}
+
+impl<'a> Bvh<'a> {
+    /// Rays passed to `intersect` must be in the objects' parent space.
+    pub fn new(objects: &[&'a dyn Hittable]) -> Self {
+        let mut bvh = Self::default();
+        let mut bounded = vec![];
+        for &o in objects {
+            let bounds = o.parent_space_bounds();
+            if bounds.is_empty() {
+                // Nothing to hit, e.g. a group without children.
+                continue;
+            }
+            if is_finite(&bounds) {
+                bounded.push((o, bounds));
+            } else {
+                bvh.unbounded.push(o);
+            }
+        }
+        bvh.stats.unbounded = bvh.unbounded.len();
+        if !bounded.is_empty() {
+            bvh.root = Some(build(bounded, 1, &mut bvh.stats));
+        }
+        bvh
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        self.stats
+    }
+
+    pub fn intersect(&self, ray: Ray) -> Vec<Intersection<'a>> {
+        let mut xs: Vec<Intersection<'a>> = self
+            .unbounded
+            .iter()
+            .flat_map(|o| o.intersect(ray))
+            .collect();
+        if let Some(root) = &self.root {
+            root.intersect(ray, &mut xs);
+        }
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        xs
+    }
+}
+
+impl<'a> Node<'a> {
+    fn intersect(&self, ray: Ray, xs: &mut Vec<Intersection<'a>>) {
+        match self {
+            Node::Leaf { bounds, objects } => {
+                if bounds.intersects(ray) {
+                    xs.extend(objects.iter().flat_map(|o| o.intersect(ray)));
+                }
+            }
+            Node::Branch {
+                bounds,
+                left,
+                right,
+            } => {
+                if bounds.intersects(ray) {
+                    left.intersect(ray, xs);
+                    right.intersect(ray, xs);
+                }
+            }
+        }
+    }
+}
+
+fn build<'a>(
+    mut items: Vec<(&'a dyn Hittable, BoundingBox)>,
+    depth: usize,
+    stats: &mut BvhStats,
+) -> Node<'a> {
+    stats.nodes += 1;
+    stats.depth = stats.depth.max(depth);
+
+    let mut bounds = BoundingBox::empty();
+    let mut centroids = BoundingBox::empty();
+    for (_, b) in items.iter() {
+        bounds.merge(b);
+        centroids.add_point(centroid(b));
+    }
+
+    let extent = [
+        centroids.max.x - centroids.min.x,
+        centroids.max.y - centroids.min.y,
+        centroids.max.z - centroids.min.z,
+    ];
+    let axis = (0..3)
+        .max_by(|a, b| extent[*a].total_cmp(&extent[*b]))
+        .unwrap();
+
+    // Objects that all share a centroid can't be told apart by splitting.
+    if items.len() <= LEAF_SIZE || extent[axis] == 0f64 {
+        stats.leaves += 1;
+        return Node::Leaf {
+            bounds,
+            objects: items.into_iter().map(|(o, _)| o).collect(),
+        };
+    }
+
+    items.sort_by(|a, b| {
+        component(centroid(&a.1), axis).total_cmp(&component(centroid(&b.1), axis))
+    });
+    let right = items.split_off(items.len() / 2);
+    Node::Branch {
+        bounds,
+        left: Box::new(build(items, depth + 1, stats)),
+        right: Box::new(build(right, depth + 1, stats)),
+    }
+}
+
+fn is_finite(b: &BoundingBox) -> bool {
+    [b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z]
+        .iter()
+        .all(|c| c.is_finite())
+}
+
+fn centroid(b: &BoundingBox) -> TypedVec {
+    TypedVec::point(
+        (b.min.x + b.max.x) / 2f64,
+        (b.min.y + b.max.y) / 2f64,
+        (b.min.z + b.max.z) / 2f64,
+    )
+}
+
+fn component(p: TypedVec, axis: usize) -> f64 {
+    match axis {
+        0 => p.x,
+        1 => p.y,
+        _ => p.z,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bvh::{Bvh, BvhStats};
+    use crate::hittable::Hittable;
+    use crate::intersection::Intersection;
+    use crate::matrix4::Matrix4;
+    use crate::plane::Plane;
+    use crate::ray::Ray;
+    use crate::sphere::Sphere;
+    use crate::vec3::TypedVec;
+
+    fn spheres<'a>(n: usize) -> Vec<Sphere<'a>> {
+        (0..n)
+            .map(|i| {
+                let mut s = Sphere::new();
+                s.transform = Some(
+                    (Matrix4::translation(3f64 * (i % 10) as f64, 3f64 * (i / 10) as f64, 0f64)
+                        * Matrix4::scaling(0.5, 0.5, 0.5))
+                    .into(),
+                );
+                s
+            })
+            .collect()
+    }
+
+    fn flat<'a>(objects: &[&'a dyn Hittable], ray: Ray) -> Vec<Intersection<'a>> {
+        let mut xs: Vec<Intersection> = objects.iter().flat_map(|o| o.intersect(ray)).collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        xs
+    }
+
+    #[test]
+    fn test_empty() {
+        let bvh = Bvh::new(&[]);
+        assert_eq!(bvh.stats(), BvhStats::default());
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        assert!(bvh.intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_stats() {
+        let s = spheres(100);
+        let objects: Vec<&dyn Hittable> = s.iter().map(|s| s as &dyn Hittable).collect();
+        let stats = Bvh::new(&objects).stats();
+        // 100 objects halve down to leaves of 3 or 4.
+        assert_eq!(stats.leaves, 32);
+        assert_eq!(stats.nodes, 63);
+        assert_eq!(stats.depth, 6);
+        assert_eq!(stats.unbounded, 0);
+    }
+
+    #[test]
+    fn test_same_as_flat() {
+        let s = spheres(100);
+        let p = Plane::default();
+        let mut objects: Vec<&dyn Hittable> = s.iter().map(|s| s as &dyn Hittable).collect();
+        objects.push(&p);
+        let bvh = Bvh::new(&objects);
+        assert_eq!(bvh.stats().unbounded, 1);
+
+        let examples = vec![
+            ("hits one", (6f64, 3f64, -5f64), (0f64, 0f64, 1f64)),
+            ("down a row", (-5f64, 6f64, 0f64), (1f64, 0f64, 0f64)),
+            ("diagonal", (-1f64, -1f64, 0f64), (1f64, 1f64, 0f64)),
+            ("misses", (1.5, 1.5, -5f64), (0f64, 0f64, 1f64)),
+            ("plane only", (50f64, 50f64, 0f64), (0f64, -1f64, 0f64)),
+        ];
+        for (name, o, d) in examples {
+            dbg!(&name);
+            let r = Ray::new(
+                TypedVec::point(o.0, o.1, o.2),
+                TypedVec::vector(d.0, d.1, d.2).normalize(),
+            );
+            let expected = flat(&objects, r);
+            let xs = bvh.intersect(r);
+            assert_eq!(xs.len(), expected.len());
+            for (x, e) in xs.iter().zip(expected.iter()) {
+                assert_eq!(x.t, e.t);
+                assert_eq!(x.obj, e.obj);
+            }
+        }
+    }
+}
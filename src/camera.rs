@@ -1,20 +1,52 @@
 use crate::canvas::Canvas;
-use crate::matrix::Matrix;
+use crate::colour::{Colour, BLACK};
+use crate::matrix4::Matrix4;
 use crate::ray::Ray;
+use crate::rng::Rng;
+use crate::sampling::{sample_disk, Adaptive, Supersampling};
+use crate::transform::Transform;
 use crate::vec3::TypedVec;
 use crate::world::World;
 use std::f64::consts::PI;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How the camera maps pixels to rays.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    /// Parallel rays; `width` is how much of the scene spans the image.
+    Orthographic { width: f64 },
+    /// Equidistant fisheye fitted to the shorter side; `fov` can pass 180°.
+    Fisheye,
+    /// Longitude across, latitude down. Ignores `fov`.
+    Equirectangular,
+}
 
 #[derive(Debug, Clone)]
 pub struct Camera {
     hsize: f64,
     vsize: f64,
     fov: f64,
+    #[allow(dead_code)]
     aspect: f64,
     half_width: f64,
     half_height: f64,
     pixel_size: f64,
-    pub transform: Matrix<f64>,
+    supersampling: Supersampling,
+    adaptive: Option<Adaptive>,
+    threads: usize,
+    aperture: f64,
+    focal_distance: f64,
+    lens_samples: usize,
+    projection: Projection,
+    shutter_open: f64,
+    shutter_close: f64,
+    shutter_samples: usize,
+    transform: Transform,
 }
 
 impl Camera {
@@ -38,28 +70,373 @@ impl Camera {
             ..Default::default()
         }
     }
+    pub fn transform(&self) -> &Matrix4 {
+        self.transform.matrix()
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = transform.into();
+    }
+
+    pub fn supersampling(&self) -> Supersampling {
+        self.supersampling
+    }
+
+    pub fn set_supersampling(&mut self, supersampling: Supersampling) {
+        self.supersampling = supersampling;
+    }
+
+    pub fn adaptive(&self) -> Option<Adaptive> {
+        self.adaptive
+    }
+
+    pub fn set_adaptive(&mut self, adaptive: Option<Adaptive>) {
+        self.adaptive = adaptive;
+    }
+
+    /// 0 is a pinhole.
+    pub fn aperture(&self) -> f64 {
+        self.aperture
+    }
+
+    pub fn focal_distance(&self) -> f64 {
+        self.focal_distance
+    }
+
+    pub fn lens_samples(&self) -> usize {
+        self.lens_samples
+    }
+
+    /// Averages `samples` rays across a thin lens focused at `focal_distance`.
+    pub fn set_depth_of_field(&mut self, aperture: f64, focal_distance: f64, samples: usize) {
+        self.aperture = aperture;
+        self.focal_distance = focal_distance;
+        self.lens_samples = samples;
+    }
+
+    pub fn shutter(&self) -> (f64, f64) {
+        (self.shutter_open, self.shutter_close)
+    }
+
+    pub fn shutter_samples(&self) -> usize {
+        self.shutter_samples
+    }
+
+    /// Fires `samples` rays at random times between `open` and `close`.
+    pub fn set_shutter(&mut self, open: f64, close: f64, samples: usize) {
+        self.shutter_open = open;
+        self.shutter_close = close;
+        self.shutter_samples = samples;
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// Depth of field only applies to `Projection::Perspective`.
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    #[cfg(test)]
     fn ray_for_pixel(&self, x: f64, y: f64) -> Ray {
-        let xoffset = (x + 0.5) * self.pixel_size;
-        let yoffset = (y + 0.5) * self.pixel_size;
+        self.ray_through(x + 0.5, y + 0.5).unwrap()
+    }
+
+    /// `None` where the projection doesn't cover the canvas.
+    fn ray_through(&self, x: f64, y: f64) -> Option<Ray> {
+        let (origin, direction) = match self.projection {
+            Projection::Perspective => return Some(self.perspective_ray(x, y)),
+            Projection::Orthographic { width } => {
+                let pixel_size = width / self.hsize;
+                let half_height = pixel_size * self.vsize / 2f64;
+                (
+                    TypedVec::point(
+                        width / 2f64 - x * pixel_size,
+                        half_height - y * pixel_size,
+                        0f64,
+                    ),
+                    TypedVec::vector(0f64, 0f64, -1f64),
+                )
+            }
+            Projection::Fisheye => {
+                let radius = self.hsize.min(self.vsize) / 2f64;
+                let nx = (x - self.hsize / 2f64) / radius;
+                let ny = (self.vsize / 2f64 - y) / radius;
+                let r = (nx * nx + ny * ny).sqrt();
+                if r > 1f64 {
+                    return None;
+                }
+                let theta = r * self.fov / 2f64;
+                let (sx, sy) = if r == 0f64 {
+                    (0f64, 0f64)
+                } else {
+                    (nx / r * theta.sin(), ny / r * theta.sin())
+                };
+                (
+                    TypedVec::point(0f64, 0f64, 0f64),
+                    TypedVec::vector(-sx, sy, -theta.cos()),
+                )
+            }
+            Projection::Equirectangular => {
+                let longitude = (x / self.hsize - 0.5) * 2f64 * PI;
+                let latitude = (0.5 - y / self.vsize) * PI;
+                (
+                    TypedVec::point(0f64, 0f64, 0f64),
+                    TypedVec::vector(
+                        -latitude.cos() * longitude.sin(),
+                        latitude.sin(),
+                        -latitude.cos() * longitude.cos(),
+                    ),
+                )
+            }
+        };
+        let inverse = self.transform.inverse().unwrap();
+        Some(Ray::new(
+            inverse * origin,
+            (inverse * direction).normalize(),
+        ))
+    }
+
+    fn perspective_ray(&self, x: f64, y: f64) -> Ray {
+        let xoffset = x * self.pixel_size;
+        let yoffset = y * self.pixel_size;
 
         let world_x = self.half_width - xoffset;
         let world_y = self.half_height - yoffset;
-        let pixel = self.transform.inverse().unwrap() * TypedVec::point(world_x, world_y, -1f64);
-        let origin = self.transform.inverse().unwrap() * TypedVec::point(0f64, 0f64, 0f64);
+        let inverse = self.transform.inverse().unwrap();
+        let pixel = inverse * TypedVec::point(world_x, world_y, -1f64);
+        let origin = inverse * TypedVec::point(0f64, 0f64, 0f64);
         let direction = (pixel - origin).normalize();
         Ray::new(origin, direction)
     }
 
+    fn lens_ray(&self, x: f64, y: f64, lens_x: f64, lens_y: f64) -> Ray {
+        let world_x = self.half_width - x * self.pixel_size;
+        let world_y = self.half_height - y * self.pixel_size;
+        let inverse = self.transform.inverse().unwrap();
+        let focus = inverse
+            * TypedVec::point(
+                world_x * self.focal_distance,
+                world_y * self.focal_distance,
+                -self.focal_distance,
+            );
+        let origin = inverse * TypedVec::point(lens_x, lens_y, 0f64);
+        Ray::new(origin, (focus - origin).normalize())
+    }
+
+    /// Averaged across the lens and shutter, keyed on (`x`, `y`).
+    fn sample_colour(&self, world: &World, x: f64, y: f64) -> Colour {
+        let lens = self.aperture > 0f64
+            && self.lens_samples > 0
+            && self.projection == Projection::Perspective;
+        let moving = self.shutter_close > self.shutter_open && self.shutter_samples > 0;
+        if !lens && !moving {
+            return match self.ray_through(x, y) {
+                Some(ray) => world.colour_at(ray.with_time(self.shutter_open), 4),
+                None => *BLACK,
+            };
+        }
+
+        let count = match (lens, moving) {
+            (true, true) => self.lens_samples.max(self.shutter_samples),
+            (true, false) => self.lens_samples,
+            _ => self.shutter_samples,
+        };
+        let radius = self.aperture / 2f64;
+        let mut rng = Rng::from_keys(&[self.supersampling.seed, x.to_bits(), y.to_bits()]);
+        let mut sum = *BLACK;
+        for _ in 0..count {
+            let ray = if lens {
+                let (u, v) = sample_disk(rng.next_f64(), rng.next_f64());
+                Some(self.lens_ray(x, y, u * radius, v * radius))
+            } else {
+                self.ray_through(x, y)
+            };
+            let time = if moving {
+                self.shutter_open + (self.shutter_close - self.shutter_open) * rng.next_f64()
+            } else {
+                self.shutter_open
+            };
+            if let Some(ray) = ray {
+                sum = sum + world.colour_at(ray.with_time(time), 4);
+            }
+        }
+        sum * (1f64 / count as f64)
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// 0 uses one thread per core.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn thread_count(&self) -> usize {
+        if self.threads > 0 {
+            return self.threads;
+        }
+        thread::available_parallelism().map_or(1, |n| n.get())
+    }
+
     pub fn render(&self, world: World) -> Canvas {
-        let mut image = Canvas::new(self.hsize as usize, self.vsize as usize);
-        for y in 0..self.vsize as usize {
-            for x in 0..self.hsize as usize {
-                let ray = self.ray_for_pixel(x as f64, y as f64);
-                let colour = world.colour_at(ray, 4);
-                image.write_pixel(x, y, colour);
+        self.render_with(world, |_| {}, &CancelToken::new()).0
+    }
+
+    /// Calls `progress` after each row and stops once `cancel` fires.
+    pub fn render_with<P>(
+        &self,
+        world: World,
+        progress: P,
+        cancel: &CancelToken,
+    ) -> (Canvas, RenderStatus)
+    where
+        P: FnMut(Progress) + Send,
+    {
+        let height = self.vsize as usize;
+        let passes = if self.adaptive.is_some() { 2 } else { 1 };
+        let tracker = Tracker {
+            progress: Mutex::new((0, progress)),
+            total_rows: height * passes,
+            started: Instant::now(),
+            cancel,
+        };
+
+        let blank = Canvas::new(self.hsize as usize, height);
+        let (image, complete) =
+            self.render_rows(blank, &tracker, |x, y| self.pixel_colour(&world, x, y));
+        if !complete {
+            return (image, RenderStatus::Cancelled);
+        }
+        match self.adaptive {
+            Some(adaptive) => {
+                let (image, complete) = self.refine(&world, image, adaptive, &tracker);
+                if complete {
+                    (image, RenderStatus::Complete)
+                } else {
+                    (image, RenderStatus::Cancelled)
+                }
+            }
+            None => (image, RenderStatus::Complete),
+        }
+    }
+
+    /// Returns whether every row was drawn.
+    fn render_rows<F, P>(
+        &self,
+        mut image: Canvas,
+        tracker: &Tracker<P>,
+        colour: F,
+    ) -> (Canvas, bool)
+    where
+        F: Fn(usize, usize) -> Colour + Sync,
+        P: FnMut(Progress) + Send,
+    {
+        let (width, height) = (image.width, image.height);
+        let next = AtomicUsize::new(0);
+        let rows: Vec<(usize, Vec<Colour>)> = thread::scope(|s| {
+            let workers: Vec<_> = (0..self.thread_count().min(height.max(1)))
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut rows = vec![];
+                        loop {
+                            if tracker.cancel.is_cancelled() {
+                                return rows;
+                            }
+                            let y = next.fetch_add(1, Ordering::Relaxed);
+                            if y >= height {
+                                return rows;
+                            }
+                            rows.push((y, (0..width).map(|x| colour(x, y)).collect()));
+                            tracker.row_done();
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().unwrap())
+                .collect()
+        });
+
+        let complete = rows.len() == height;
+        for (y, row) in rows {
+            for (x, c) in row.into_iter().enumerate() {
+                image.write_pixel(x, y, c);
             }
         }
-        image
+        (image, complete)
+    }
+
+    /// Re-renders pixels of `first` that differ too much from a neighbour.
+    fn refine<P>(
+        &self,
+        world: &World,
+        first: Canvas,
+        adaptive: Adaptive,
+        tracker: &Tracker<P>,
+    ) -> (Canvas, bool)
+    where
+        P: FnMut(Progress) + Send,
+    {
+        let base = first.clone();
+        self.render_rows(base, tracker, |x, y| {
+            let centre = first.get(x, y).unwrap();
+            let neighbours = [
+                x.checked_sub(1).and_then(|x| first.get(x, y)),
+                first.get(x + 1, y),
+                y.checked_sub(1).and_then(|y| first.get(x, y)),
+                first.get(x, y + 1),
+            ];
+            let mut colours = vec![centre];
+            colours.extend(neighbours.iter().flatten());
+            if adaptive.exceeds(&colours) {
+                self.adaptive_colour(world, adaptive, x as f64, y as f64, 1f64, 0)
+            } else {
+                centre
+            }
+        })
+    }
+
+    fn adaptive_colour(
+        &self,
+        world: &World,
+        adaptive: Adaptive,
+        x: f64,
+        y: f64,
+        size: f64,
+        depth: usize,
+    ) -> Colour {
+        let corners: Vec<Colour> = [(x, y), (x + size, y), (x, y + size), (x + size, y + size)]
+            .iter()
+            .map(|(px, py)| self.sample_colour(world, *px, *py))
+            .collect();
+        if depth < adaptive.max_depth && adaptive.exceeds(&corners) {
+            let half = size / 2f64;
+            let quarters = [(x, y), (x + half, y), (x, y + half), (x + half, y + half)]
+                .iter()
+                .fold(*BLACK, |sum, (qx, qy)| {
+                    sum + self.adaptive_colour(world, adaptive, *qx, *qy, half, depth + 1)
+                });
+            return quarters * 0.25;
+        }
+        corners.into_iter().fold(*BLACK, |sum, c| sum + c) * 0.25
+    }
+
+    fn pixel_colour(&self, world: &World, x: usize, y: usize) -> Colour {
+        let mut sum = *BLACK;
+        let mut total = 0f64;
+        for (dx, dy, weight) in self.supersampling.offsets(x, y) {
+            let colour = self.sample_colour(world, x as f64 + 0.5 + dx, y as f64 + 0.5 + dy);
+            sum = sum + colour * weight;
+            total += weight;
+        }
+        if total == 0f64 {
+            return *BLACK;
+        }
+        sum * (1f64 / total)
     }
 }
 
@@ -75,41 +452,130 @@ impl Default for Camera {
             half_width: half_view,
             aspect: 1f64,
             pixel_size: (half_view * 2f64) / 100f64,
-            transform: Matrix::identity(4),
+            supersampling: Supersampling::default(),
+            adaptive: None,
+            threads: 0,
+            aperture: 0f64,
+            focal_distance: 1f64,
+            lens_samples: 1,
+            projection: Projection::Perspective,
+            shutter_open: 0f64,
+            shutter_close: 0f64,
+            shutter_samples: 1,
+            transform: Matrix4::identity().into(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Progress {
+    pub rows_done: usize,
+    /// Twice the height with the adaptive pass on.
+    pub total_rows: usize,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f64 {
+        if self.total_rows == 0 {
+            return 1f64;
         }
+        self.rows_done as f64 / self.total_rows as f64
     }
+
+    pub fn eta(&self) -> Option<Duration> {
+        if self.rows_done == 0 {
+            return None;
+        }
+        let remaining = self.total_rows.saturating_sub(self.rows_done);
+        Some(
+            self.elapsed
+                .mul_f64(remaining as f64 / self.rows_done as f64),
+        )
+    }
+}
+
+/// Stops a render from another thread; clones share the flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderStatus {
+    Complete,
+    Cancelled,
 }
 
-pub fn view_transform(from: TypedVec, to: TypedVec, up: TypedVec) -> Matrix<f64> {
+/// Counts finished rows, calling back with the count still locked.
+struct Tracker<'t, P> {
+    progress: Mutex<(usize, P)>,
+    total_rows: usize,
+    started: Instant,
+    cancel: &'t CancelToken,
+}
+
+impl<'t, P: FnMut(Progress)> Tracker<'t, P> {
+    fn row_done(&self) {
+        let mut guard = self.progress.lock().unwrap_or_else(PoisonError::into_inner);
+        let (rows_done, callback) = &mut *guard;
+        *rows_done += 1;
+        callback(Progress {
+            rows_done: *rows_done,
+            total_rows: self.total_rows,
+            elapsed: self.started.elapsed(),
+        });
+    }
+}
+
+pub fn view_transform(from: TypedVec, to: TypedVec, up: TypedVec) -> Matrix4 {
     let forward = (to - from).normalize();
     let left = forward.cross_product(up.normalize());
     let true_up = left.cross_product(forward);
-    let orientation = Matrix::from_iter(
-        4,
-        4,
-        vec![
-            left.x, left.y, left.z, 0f64, true_up.x, true_up.y, true_up.z, 0f64, -forward.x,
-            -forward.y, -forward.z, 0f64, 0f64, 0f64, 0f64, 1f64,
-        ],
-    );
-    orientation * Matrix::translation(-from.x, -from.y, -from.z)
+    let orientation = Matrix4::new([
+        [left.x, left.y, left.z, 0f64],
+        [true_up.x, true_up.y, true_up.z, 0f64],
+        [-forward.x, -forward.y, -forward.z, 0f64],
+        [0f64, 0f64, 0f64, 1f64],
+    ]);
+    orientation * Matrix4::translation(-from.x, -from.y, -from.z)
 }
 
 #[cfg(test)]
 mod test {
-    use crate::camera::{view_transform, Camera};
+    use crate::camera::{view_transform, Camera, CancelToken, Progress, Projection, RenderStatus};
     use crate::colour::Colour;
+    use crate::csg::Csg;
     use crate::default_world;
-    use crate::matrix::{Axis, Matrix};
+    use crate::group::Group;
+    use crate::matrix::Axis;
+    use crate::matrix4::Matrix4;
+    use crate::motion::Motion;
+    use crate::sampling::{Adaptive, Filter, SamplePattern, Supersampling};
+    use crate::sphere::Sphere;
     use crate::vec3::TypedVec;
+    use crate::world::World;
     use std::f64::consts::PI;
+    use std::time::Duration;
 
     #[test]
     fn test_default() {
         let from = TypedVec::point(0f64, 0f64, 0f64);
         let to = TypedVec::point(0f64, 0f64, -1f64);
         let up = TypedVec::vector(0f64, 1f64, 0f64);
-        assert_eq!(view_transform(from, to, up), Matrix::identity(4))
+        assert_eq!(view_transform(from, to, up), Matrix4::identity())
     }
 
     #[test]
@@ -119,7 +585,7 @@ mod test {
         let up = TypedVec::vector(0f64, 1f64, 0f64);
         assert_eq!(
             view_transform(from, to, up),
-            Matrix::scaling(-1f64, 1f64, -1f64)
+            Matrix4::scaling(-1f64, 1f64, -1f64)
         )
     }
 
@@ -130,7 +596,7 @@ mod test {
         let up = TypedVec::vector(0f64, 1f64, 0f64);
         assert_eq!(
             view_transform(from, to, up),
-            Matrix::translation(0f64, 0f64, -8f64)
+            Matrix4::translation(0f64, 0f64, -8f64)
         )
     }
 
@@ -141,14 +607,12 @@ mod test {
         let up = TypedVec::vector(1f64, 1f64, 0f64);
         assert_eq!(
             view_transform(from, to, up).round(100000f64),
-            Matrix::from_iter(
-                4,
-                4,
-                vec![
-                    -0.50709, 0.50709, 0.67612, -2.36643, 0.76772, 0.60609, 0.12122, -2.82843,
-                    -0.35857, 0.59761, -0.71714, 0.00000, 0.00000, 0.00000, 0.00000, 1.00000
-                ]
-            )
+            Matrix4::new([
+                [-0.50709, 0.50709, 0.67612, -2.36643],
+                [0.76772, 0.60609, 0.12122, -2.82843],
+                [-0.35857, 0.59761, -0.71714, 0.00000],
+                [0.00000, 0.00000, 0.00000, 1.00000]
+            ])
         )
     }
 
@@ -191,7 +655,9 @@ mod test {
     #[test]
     fn test_ray_with_transformed_camera() {
         let mut c = Camera::new(201f64, 101f64, PI / 2f64);
-        c.transform = Matrix::rotation(Axis::Y, PI / 4f64) * Matrix::translation(0f64, -2f64, 5f64);
+        c.set_transform(
+            Matrix4::rotation(Axis::Y, PI / 4f64) * Matrix4::translation(0f64, -2f64, 5f64),
+        );
         let r = c.ray_for_pixel(100f64, 50f64);
         assert_eq!(r.origin, TypedVec::point(0f64, 2f64, -5f64));
         assert_eq!(
@@ -204,15 +670,384 @@ mod test {
     fn render_a_world() {
         default_world!(w, s1, s2);
         let mut c = Camera::new(11f64, 11f64, PI / 2f64);
-        c.transform = view_transform(
+        c.set_transform(view_transform(
             TypedVec::point(0f64, 0f64, -5f64),
             TypedVec::point(0f64, 0f64, 0f64),
             TypedVec::vector(0f64, 1f64, 0f64),
-        );
+        ));
         let image = c.render(w);
         assert_eq!(
             image.get(5, 5).unwrap().round(100000f64),
             Colour::new(0.38066, 0.47582, 0.28549)
         )
     }
+
+    #[test]
+    fn test_supersampled_edge_pixel() {
+        default_world!(w, s1, s2);
+        let mut c = Camera::new(11f64, 11f64, PI / 2f64);
+        c.set_transform(view_transform(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+        ));
+        // The sphere's silhouette crosses this pixel.
+        let single = c.pixel_colour(&w, 6, 5);
+
+        let mut s = Supersampling::new(4, SamplePattern::Jittered, Filter::Tent);
+        s.seed = 7;
+        c.set_supersampling(s);
+        let first = c.pixel_colour(&w, 6, 5);
+        assert_ne!(first, single);
+        assert_eq!(c.pixel_colour(&w, 6, 5), first);
+
+        s.seed = 8;
+        c.set_supersampling(s);
+        assert_ne!(c.pixel_colour(&w, 6, 5), first);
+
+        // Pixels well inside the background are unaffected.
+        assert_eq!(c.pixel_colour(&w, 0, 0), Colour::new(0f64, 0f64, 0f64));
+    }
+
+    #[test]
+    fn test_adaptive_refines_edges_only() {
+        let mut c = Camera::new(11f64, 11f64, PI / 2f64);
+        c.set_transform(view_transform(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+        ));
+        let first = {
+            default_world!(w, s1, s2);
+            c.render(w)
+        };
+        c.set_adaptive(Some(Adaptive::new(0.1, 2)));
+        let refined = {
+            default_world!(w, s1, s2);
+            c.render(w)
+        };
+
+        // Flat background is left alone.
+        assert_eq!(refined.get(0, 0), first.get(0, 0));
+        assert_eq!(refined.get(10, 10), first.get(10, 10));
+        // The sphere's silhouette is refined.
+        assert_ne!(refined.get(6, 5), first.get(6, 5));
+
+        c.set_adaptive(Some(Adaptive::new(2f64, 2)));
+        let untouched = {
+            default_world!(w, s1, s2);
+            c.render(w)
+        };
+        for y in 0..11 {
+            for x in 0..11 {
+                assert_eq!(untouched.get(x, y), first.get(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_threads_match_single_threaded() {
+        let mut c = Camera::new(21f64, 13f64, PI / 2f64);
+        c.set_transform(view_transform(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+        ));
+        c.set_supersampling(Supersampling::new(
+            2,
+            SamplePattern::Jittered,
+            Filter::Mitchell,
+        ));
+        c.set_adaptive(Some(Adaptive::default()));
+
+        c.set_threads(1);
+        let single = {
+            default_world!(w, s1, s2);
+            c.render(w)
+        };
+        for threads in [2, 5, 64] {
+            dbg!(&threads);
+            c.set_threads(threads);
+            let multi = {
+                default_world!(w, s1, s2);
+                c.render(w)
+            };
+            for y in 0..13 {
+                for x in 0..21 {
+                    assert_eq!(multi.get(x, y), single.get(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_scene_is_thread_safe() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<World>();
+        assert_send_sync::<Group>();
+        assert_send_sync::<Csg>();
+        assert_send_sync::<Camera>();
+    }
+
+    #[test]
+    fn test_render_reports_progress() {
+        let mut c = Camera::new(11f64, 7f64, PI / 2f64);
+        c.set_threads(3);
+        c.set_adaptive(Some(Adaptive::default()));
+        let mut seen = vec![];
+        let (image, status) = {
+            default_world!(w, s1, s2);
+            c.render_with(w, |p| seen.push(p), &CancelToken::new())
+        };
+        assert_eq!(status, RenderStatus::Complete);
+        assert_eq!(
+            seen.iter().map(|p| p.rows_done).collect::<Vec<usize>>(),
+            (1..=14).collect::<Vec<usize>>()
+        );
+        assert!(seen.iter().all(|p| p.total_rows == 14));
+        assert_eq!(seen.last().unwrap().eta(), Some(Duration::ZERO));
+
+        let expected = {
+            default_world!(w, s1, s2);
+            c.render(w)
+        };
+        for y in 0..7 {
+            for x in 0..11 {
+                assert_eq!(image.get(x, y), expected.get(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_cancel_render() {
+        let mut c = Camera::new(11f64, 11f64, PI / 2f64);
+        c.set_transform(view_transform(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+        ));
+        c.set_threads(1);
+        let full = {
+            default_world!(w, s1, s2);
+            c.render(w)
+        };
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let mut calls = 0;
+        let (image, status) = {
+            default_world!(w, s1, s2);
+            c.render_with(w, |_| calls += 1, &cancel)
+        };
+        assert_eq!(status, RenderStatus::Cancelled);
+        assert_eq!(calls, 0);
+        assert_eq!(image.get(5, 5), Some(Colour::new(0f64, 0f64, 0f64)));
+
+        // Cancelled from the callback once six rows are done.
+        let cancel = CancelToken::new();
+        let handle = cancel.clone();
+        let (image, status) = {
+            default_world!(w, s1, s2);
+            c.render_with(
+                w,
+                |p| {
+                    if p.rows_done == 6 {
+                        handle.cancel();
+                    }
+                },
+                &cancel,
+            )
+        };
+        assert_eq!(status, RenderStatus::Cancelled);
+        assert_eq!(image.get(5, 5), full.get(5, 5));
+        assert_ne!(full.get(5, 6), Some(Colour::new(0f64, 0f64, 0f64)));
+        assert_eq!(image.get(5, 6), Some(Colour::new(0f64, 0f64, 0f64)));
+    }
+
+    #[test]
+    fn test_progress_eta() {
+        let examples = vec![
+            ("not started", 0, 10, 0, None, 0f64),
+            ("a quarter", 5, 20, 10, Some(30), 0.25),
+            ("done", 4, 4, 8, Some(0), 1f64),
+            ("empty", 0, 0, 0, None, 1f64),
+        ];
+        for (name, rows_done, total_rows, secs, eta, fraction) in examples {
+            dbg!(&name);
+            let p = Progress {
+                rows_done,
+                total_rows,
+                elapsed: Duration::from_secs(secs),
+            };
+            assert_eq!(p.eta(), eta.map(Duration::from_secs));
+            assert_eq!(p.fraction(), fraction);
+        }
+    }
+
+    #[test]
+    fn test_lens_rays_meet_on_focal_plane() {
+        let mut c = Camera::new(201f64, 101f64, PI / 2f64);
+        c.set_depth_of_field(0.5, 4f64, 8);
+        let centre = c.lens_ray(30.5, 70.5, 0f64, 0f64);
+        let examples = vec![
+            ("centre", (0f64, 0f64)),
+            ("left", (-0.25, 0f64)),
+            ("up", (0f64, 0.25)),
+            ("diagonal", (0.1, -0.2)),
+        ];
+        for (name, (lx, ly)) in examples {
+            dbg!(&name);
+            let r = c.lens_ray(30.5, 70.5, lx, ly);
+            assert_eq!(r.origin, TypedVec::point(lx, ly, 0f64));
+            // Where the ray crosses z = -4.
+            let t = -4f64 / r.direction.z;
+            assert_eq!(
+                r.position(t).round(100000f64),
+                centre.position(-4f64 / centre.direction.z).round(100000f64)
+            );
+        }
+        assert_eq!(
+            centre.direction.round(100000f64),
+            c.ray_for_pixel(30f64, 70f64).direction.round(100000f64)
+        );
+    }
+
+    #[test]
+    fn test_depth_of_field_blurs_out_of_focus() {
+        let mut c = Camera::new(11f64, 11f64, PI / 2f64);
+        c.set_transform(view_transform(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+        ));
+        default_world!(w, s1, s2);
+        let sharp = c.pixel_colour(&w, 6, 5);
+
+        // No aperture is still a pinhole.
+        c.set_depth_of_field(0f64, 10f64, 16);
+        assert_eq!(c.pixel_colour(&w, 6, 5), sharp);
+
+        // Focused well behind the sphere, its edge smears.
+        c.set_depth_of_field(0.5, 20f64, 16);
+        let blurred = c.pixel_colour(&w, 6, 5);
+        assert_ne!(blurred, sharp);
+        assert_eq!(c.pixel_colour(&w, 6, 5), blurred);
+    }
+
+    #[test]
+    fn test_orthographic_rays() {
+        let mut c = Camera::new(200f64, 100f64, PI / 2f64);
+        c.set_projection(Projection::Orthographic { width: 4f64 });
+        let examples = vec![
+            ("centre", (100f64, 50f64), (0f64, 0f64)),
+            ("top left", (0f64, 0f64), (2f64, 1f64)),
+            ("bottom right", (200f64, 100f64), (-2f64, -1f64)),
+        ];
+        for (name, (x, y), (ox, oy)) in examples {
+            dbg!(&name);
+            let r = c.ray_through(x, y).unwrap();
+            assert_eq!(r.origin, TypedVec::point(ox, oy, 0f64));
+            assert_eq!(r.direction, TypedVec::vector(0f64, 0f64, -1f64));
+        }
+
+        c.set_transform(view_transform(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+        ));
+        let r = c.ray_through(100f64, 50f64).unwrap();
+        assert_eq!(r.origin, TypedVec::point(0f64, 0f64, -5f64));
+        assert_eq!(r.direction, TypedVec::vector(0f64, 0f64, 1f64));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn test_fisheye_rays() {
+        let mut c = Camera::new(200f64, 100f64, PI);
+        c.set_projection(Projection::Fisheye);
+        let examples = vec![
+            ("centre", (100f64, 50f64), Some((0f64, 0f64, -1f64))),
+            (
+                "halfway right",
+                (125f64, 50f64),
+                Some((-0.70711, 0f64, -0.70711)),
+            ),
+            ("top edge", (100f64, 0f64), Some((0f64, 1f64, 0f64))),
+            ("left edge", (50f64, 50f64), Some((1f64, 0f64, 0f64))),
+            ("outside circle", (10f64, 50f64), None),
+            ("corner", (0f64, 0f64), None),
+        ];
+        for (name, (x, y), expected) in examples {
+            dbg!(&name);
+            let r = c.ray_through(x, y);
+            assert_eq!(
+                r.map(|r| r.direction.round(100000f64)),
+                expected.map(|(dx, dy, dz)| TypedVec::vector(dx, dy, dz))
+            );
+        }
+    }
+
+    #[test]
+    fn test_equirectangular_rays() {
+        let mut c = Camera::new(360f64, 180f64, PI / 2f64);
+        c.set_projection(Projection::Equirectangular);
+        let examples = vec![
+            ("forward", (180f64, 90f64), (0f64, 0f64, -1f64)),
+            ("behind", (0f64, 90f64), (0f64, 0f64, 1f64)),
+            ("right", (270f64, 90f64), (-1f64, 0f64, 0f64)),
+            ("left", (90f64, 90f64), (1f64, 0f64, 0f64)),
+            ("up", (180f64, 0f64), (0f64, 1f64, 0f64)),
+            ("down", (123f64, 180f64), (0f64, -1f64, 0f64)),
+        ];
+        for (name, (x, y), (dx, dy, dz)) in examples {
+            dbg!(&name);
+            let r = c.ray_through(x, y).unwrap();
+            assert_eq!(r.origin, TypedVec::point(0f64, 0f64, 0f64));
+            assert_eq!(r.direction.round(100000f64), TypedVec::vector(dx, dy, dz));
+        }
+    }
+
+    #[test]
+    fn test_fisheye_render_blacks_out_corners() {
+        let mut c = Camera::new(11f64, 11f64, PI);
+        c.set_projection(Projection::Fisheye);
+        c.set_transform(view_transform(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+        ));
+        default_world!(w, s1, s2);
+        assert_eq!(c.pixel_colour(&w, 0, 0), Colour::new(0f64, 0f64, 0f64));
+        assert_ne!(c.pixel_colour(&w, 5, 5), Colour::new(0f64, 0f64, 0f64));
+    }
+
+    #[test]
+    fn test_motion_blur() {
+        let mut c = Camera::new(11f64, 11f64, PI / 2f64);
+        c.set_transform(view_transform(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+        ));
+        let mut s = Sphere::new();
+        s.motion = Some(Motion::linear(
+            Matrix4::identity(),
+            Matrix4::translation(-1f64, 0f64, 0f64),
+        ));
+        let mut w = World::default();
+        w.objects.push(&s);
+
+        // The sphere's right edge only covers this pixel at the start.
+        let sharp = c.pixel_colour(&w, 6, 5);
+        assert_ne!(sharp, Colour::new(0f64, 0f64, 0f64));
+        c.set_shutter(1f64, 1f64, 1);
+        assert_eq!(c.pixel_colour(&w, 6, 5), Colour::new(0f64, 0f64, 0f64));
+
+        c.set_shutter(0f64, 1f64, 32);
+        let blurred = c.pixel_colour(&w, 6, 5);
+        assert_ne!(blurred, sharp);
+        assert_ne!(blurred, Colour::new(0f64, 0f64, 0f64));
+        assert!(blurred.red < sharp.red);
+        assert_eq!(c.pixel_colour(&w, 6, 5), blurred);
+    }
 }
@@ -1,11 +1,38 @@
 use crate::colour::Colour;
-use std::fmt::Write;
+use crate::hdr;
+use crate::png;
+use crate::ppm::{self, PpmFormat};
+use crate::tonemap::PostProcess;
+use anyhow::Result;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd)]
+pub enum BitDepth {
+    Eight,
+    Sixteen,
+}
+
+impl BitDepth {
+    pub fn max(self) -> u16 {
+        match self {
+            BitDepth::Eight => 255,
+            BitDepth::Sixteen => 65535,
+        }
+    }
+
+    pub(crate) fn bytes(self) -> usize {
+        match self {
+            BitDepth::Eight => 1,
+            BitDepth::Sixteen => 2,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
     pixels: Vec<Colour>,
+    post_process: PostProcess,
 }
 
 impl Canvas {
@@ -17,49 +44,102 @@ impl Canvas {
             width,
             height,
             pixels,
+            post_process: PostProcess::default(),
         }
     }
 
+    /// Channels are scaled to `[0, 1]`.
+    pub fn from_ppm<R: std::io::Read>(mut input: R) -> Result<Self> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let (width, height, pixels) = ppm::decode(&data)?;
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            post_process: PostProcess::default(),
+        })
+    }
+
+    pub fn post_process(&self) -> PostProcess {
+        self.post_process
+    }
+
+    /// Applied by `save`, `write_ppm` and `write_png` only.
+    pub fn set_post_process(&mut self, post_process: PostProcess) {
+        self.post_process = post_process;
+    }
+
     pub fn save(&self) -> String {
-        let mut s = format!("P3\n{} {}\n255\n", self.width, self.height);
-        for y in 0..self.height {
-            let mut l = String::new();
-            for x in 0..self.width {
-                if l.len() >= 58 {
-                    s.push_str(l.as_str());
-                    s.push('\n');
-                    l.truncate(0)
-                }
-                write!(l, "{} ", self.pixels[(x + y * self.width) as usize]).unwrap();
-            }
-            s.push_str(l.as_str());
-            s.push('\n');
-        }
+        let mut out = Vec::new();
+        self.write_ppm(&mut out, PpmFormat::Ascii, BitDepth::Eight)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
 
-        s.push('\n');
-        s
+    /// Post-processed, then clamped and rounded like `Colour`'s `Display`.
+    pub fn write_ppm<W: std::io::Write>(
+        &self,
+        out: W,
+        format: PpmFormat,
+        depth: BitDepth,
+    ) -> Result<()> {
+        ppm::encode(
+            out,
+            self.width,
+            self.height,
+            format,
+            depth,
+            self.pixels
+                .iter()
+                .map(|p| self.post_process.apply(*p).quantise(depth.max())),
+        )
+    }
+
+    pub fn write_png<W: std::io::Write>(&self, mut out: W, depth: BitDepth) -> Result<()> {
+        let samples: Vec<u16> = self
+            .pixels
+            .iter()
+            .flat_map(|p| self.post_process.apply(*p).quantise(depth.max()))
+            .collect();
+        png::encode(&mut out, self.width, self.height, depth, &samples)
+    }
+
+    /// Keeps values above 1.
+    pub fn write_hdr<W: std::io::Write>(&self, out: W) -> Result<()> {
+        hdr::encode_rgbe(out, self.width, self.height, &self.pixels)
+    }
+
+    /// Each channel as an `f32`, exactly as rendered.
+    pub fn write_pfm<W: std::io::Write>(&self, out: W) -> Result<()> {
+        hdr::encode_pfm(out, self.width, self.height, &self.pixels)
     }
 
     pub fn fill(&mut self, colour: Colour) {
-        (0..self.width * self.height).for_each(|n| self.pixels[n as usize] = colour);
+        (0..self.width * self.height).for_each(|n| self.pixels[n] = colour);
     }
 
     pub fn write_pixel(&mut self, x: usize, y: usize, colour: Colour) {
         if (x >= self.width) || (y >= self.height) {
             return;
         }
-        self.pixels[(x + y * self.width) as usize] = colour;
+        self.pixels[x + y * self.width] = colour;
     }
 
     pub fn get(&self, x: usize, y: usize) -> Option<Colour> {
-        Some(self.pixels[(x + y * self.width) as usize])
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.pixels[x + y * self.width])
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::canvas::Canvas;
+    use crate::canvas::{BitDepth, Canvas};
     use crate::colour::Colour;
+    use crate::ppm::PpmFormat;
+    use crate::tonemap::{PostProcess, ToneMap, Transfer};
 
     #[test]
     fn test_create() {
@@ -81,4 +161,139 @@ mod tests {
         c.fill(Colour::new(1.0, 0.8, 0.6));
         dbg!(&c.save());
     }
+
+    #[test]
+    fn test_save() {
+        let mut c = Canvas::new(5, 3);
+        c.write_pixel(0, 0, Colour::new(1.5, 0.0, 0.0));
+        c.write_pixel(2, 1, Colour::new(0.0, 0.5, 0.0));
+        c.write_pixel(4, 2, Colour::new(-0.5, 0.0, 1.0));
+        assert_eq!(
+            c.save(),
+            "P3\n5 3\n255\n\
+             255 0 0 0 0 0 0 0 0 0 0 0 0 0 0 \n\
+             0 0 0 0 0 0 0 128 0 0 0 0 0 0 0 \n\
+             0 0 0 0 0 0 0 0 0 0 0 0 0 0 255 \n\n"
+        );
+
+        let mut c = Canvas::new(10, 2);
+        c.fill(Colour::new(1.0, 0.8, 0.6));
+        let saved = c.save();
+        let lines: Vec<_> = saved.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(
+            lines[3],
+            "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 "
+        );
+        assert_eq!(
+            lines[4],
+            "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 "
+        );
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Colour::new(1.0, 0.5, 0.0));
+        let mut out = Vec::new();
+        c.write_ppm(&mut out, PpmFormat::Binary, BitDepth::Sixteen)
+            .unwrap();
+        assert_eq!(
+            out,
+            b"P6\n2 1\n65535\n\xff\xff\x80\x00\x00\x00\x00\x00\x00\x00\x00\x00"
+        );
+    }
+
+    #[test]
+    fn test_write_png() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(0, 0, Colour::new(1.5, 0.0, -0.5));
+        c.write_pixel(2, 1, Colour::new(0.5, 0.8, 0.6));
+        let examples = vec![
+            ("eight", BitDepth::Eight, 8),
+            ("sixteen", BitDepth::Sixteen, 16),
+        ];
+        for (name, depth, bits) in examples {
+            dbg!(&name);
+            let mut out = Vec::new();
+            c.write_png(&mut out, depth).unwrap();
+            assert_eq!(&out[1..4], b"PNG");
+            assert_eq!(&out[16..25], &[0, 0, 0, 3, 0, 0, 0, 2, bits]);
+        }
+    }
+
+    #[test]
+    fn test_from_ppm() {
+        let mut c = Canvas::new(4, 3);
+        c.write_pixel(0, 0, Colour::new(1.0, 0.5, 0.0));
+        c.write_pixel(3, 1, Colour::new(0.2, 0.4, 0.6));
+        c.write_pixel(1, 2, Colour::new(2.0, -1.0, 0.8));
+        let examples = vec![
+            ("ascii", PpmFormat::Ascii, BitDepth::Eight),
+            ("ascii sixteen", PpmFormat::Ascii, BitDepth::Sixteen),
+            ("binary", PpmFormat::Binary, BitDepth::Eight),
+            ("binary sixteen", PpmFormat::Binary, BitDepth::Sixteen),
+        ];
+        for (name, format, depth) in examples {
+            dbg!(&name);
+            let mut out = Vec::new();
+            c.write_ppm(&mut out, format, depth).unwrap();
+            let back = Canvas::from_ppm(&out[..]).unwrap();
+            assert_eq!((back.width, back.height), (4, 3));
+            for y in 0..3 {
+                for x in 0..4 {
+                    let (a, b) = (c.get(x, y).unwrap(), back.get(x, y).unwrap());
+                    assert_eq!(a.quantise(depth.max()), b.quantise(depth.max()));
+                }
+            }
+        }
+        assert_eq!(
+            Canvas::from_ppm(&b"P6\n2 2\n255\n\x00\x00"[..])
+                .unwrap_err()
+                .to_string(),
+            "pixel data is truncated: expected 12 bytes, found 2"
+        );
+    }
+
+    #[test]
+    fn test_write_hdr() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Colour::new(12.0, 3.0, 0.75));
+        c.write_pixel(1, 0, Colour::new(0.5, 0.25, 0.0));
+
+        let mut out = Vec::new();
+        c.write_hdr(&mut out).unwrap();
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n";
+        assert_eq!(&out[..header.len()], &header[..]);
+        assert_eq!(&out[header.len()..], &[192, 48, 12, 132, 128, 64, 0, 128]);
+
+        let mut out = Vec::new();
+        c.write_pfm(&mut out).unwrap();
+        assert_eq!(&out[..12], b"PF\n2 1\n-1.0\n");
+        assert_eq!(&out[12..16], &12f32.to_le_bytes());
+        assert_eq!(out.len(), 12 + 2 * 3 * 4);
+    }
+
+    #[test]
+    fn test_post_process() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Colour::new(0.5, 1.0, 4.0));
+        c.set_post_process(PostProcess::new(0.0, ToneMap::Reinhard, Transfer::Srgb));
+        assert_eq!(c.save(), "P3\n2 1\n255\n156 188 231 0 0 0 \n\n");
+        assert_eq!(c.get(0, 0).unwrap(), Colour::new(0.5, 1.0, 4.0));
+
+        let mut png = Vec::new();
+        c.write_png(&mut png, BitDepth::Eight).unwrap();
+        c.set_post_process(PostProcess::default());
+        let mut plain = Vec::new();
+        c.write_png(&mut plain, BitDepth::Eight).unwrap();
+        assert_ne!(png, plain);
+
+        let mut hdr = Vec::new();
+        c.write_hdr(&mut hdr).unwrap();
+        c.set_post_process(PostProcess::new(2.0, ToneMap::Aces, Transfer::Srgb));
+        let mut again = Vec::new();
+        c.write_hdr(&mut again).unwrap();
+        assert_eq!(hdr, again);
+    }
 }
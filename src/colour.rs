@@ -18,6 +18,12 @@ impl Colour {
         true
     }
 
+    /// Clamped to `[0, 1]` and scaled to the nearest of `max` steps.
+    pub fn quantise(&self, max: u16) -> [u16; 3] {
+        let scale = |val: f64| (val.clamp(0.0, 1.0) * f64::from(max)).round() as u16;
+        [scale(self.red), scale(self.green), scale(self.blue)]
+    }
+
     #[cfg(test)]
     pub(crate) fn round(&self, factor: f64) -> Self {
         Self {
@@ -40,23 +46,8 @@ impl Default for Colour {
 
 impl std::fmt::Display for Colour {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fn clamp(val: f64) -> f64 {
-            if val < 0f64 {
-                0.0
-            } else if val > 1f64 {
-                1.0
-            } else {
-                val
-            }
-        }
-
-        write!(
-            f,
-            "{} {} {}",
-            (clamp(self.red) * 255f64).round(),
-            (clamp(self.green) * 255f64).round(),
-            (clamp(self.blue) * 255f64).round()
-        )
+        let [red, green, blue] = self.quantise(255);
+        write!(f, "{} {} {}", red, green, blue)
     }
 }
 
@@ -135,4 +126,24 @@ mod test {
         let c2 = Colour::new(0.7, 0.1, 0.25);
         assert_eq!(c1 + c2, Colour::new(1.6, 0.7, 1.0))
     }
+
+    #[test]
+    fn test_quantise() {
+        let examples = vec![
+            ("black", Colour::new(0.0, 0.0, 0.0), 255, [0, 0, 0]),
+            ("clamped", Colour::new(-0.5, 1.5, 1.0), 255, [0, 255, 255]),
+            ("rounded", Colour::new(0.5, 0.8, 0.6), 255, [128, 204, 153]),
+            (
+                "sixteen",
+                Colour::new(0.5, 0.8, 1.5),
+                65535,
+                [32768, 52428, 65535],
+            ),
+        ];
+        for (name, colour, max, expected) in examples {
+            dbg!(&name);
+            assert_eq!(colour.quantise(max), expected);
+        }
+        assert_eq!(format!("{}", Colour::new(0.5, 0.8, 0.6)), "128 204 153");
+    }
 }
@@ -1,22 +1,22 @@
+use crate::bounds::BoundingBox;
 use crate::cylinder::Capped;
 use crate::hittable::HittableImpl;
 use crate::intersection::Intersection;
 use crate::material::Material;
-use crate::matrix::Matrix;
 use crate::ray::Ray;
 use crate::vec3::TypedVec;
 use crate::{shape, ZeroIsh, EPSILON};
 use anyhow::Result;
-use std::f64::INFINITY;
 
 shape!(Cone, nodefault, minimum -> f64, maximum -> f64, closed -> bool);
 
 impl<'a> Default for Cone<'a> {
     fn default() -> Self {
         Self {
-            minimum: -INFINITY,
-            maximum: INFINITY,
+            minimum: -f64::INFINITY,
+            maximum: f64::INFINITY,
             transform: None,
+            motion: None,
             parent: None,
             material: Material::default(),
             closed: false,
@@ -25,7 +25,7 @@ impl<'a> Default for Cone<'a> {
 }
 
 impl<'a> Cone<'a> {
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
         let a = ray.direction.x.powi(2) - ray.direction.y.powi(2) + ray.direction.z.powi(2);
         let b = 2.0 * ray.origin.x * ray.direction.x - 2.0 * ray.origin.y * ray.direction.y
             + 2.0 * ray.origin.z * ray.direction.z;
@@ -75,6 +75,14 @@ impl<'a> Cone<'a> {
             Ok(TypedVec::vector(p.x, y, p.z))
         }
     }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(
+            TypedVec::point(-limit, self.minimum, -limit),
+            TypedVec::point(limit, self.maximum, limit),
+        )
+    }
 }
 
 impl<'a> Capped for Cone<'a> {
@@ -101,6 +109,7 @@ impl<'a> Capped for Cone<'a> {
 mod test {
     use crate::cone::Cone;
     use crate::hittable::Hittable;
+    use crate::hittable::HittableImpl;
     use crate::ray::Ray;
     use crate::roundf;
     use crate::vec3::TypedVec;
@@ -219,4 +228,26 @@ mod test {
             assert_eq!(c.local_normal_at(p).unwrap(), n)
         }
     }
+
+    #[test]
+    fn test_bounds() {
+        let b = Cone::default().bounds();
+        assert_eq!(
+            b.min,
+            TypedVec::point(-f64::INFINITY, -f64::INFINITY, -f64::INFINITY)
+        );
+        assert_eq!(
+            b.max,
+            TypedVec::point(f64::INFINITY, f64::INFINITY, f64::INFINITY)
+        );
+
+        let c = Cone {
+            minimum: -5f64,
+            maximum: 3f64,
+            ..Default::default()
+        };
+        let b = c.bounds();
+        assert_eq!(b.min, TypedVec::point(-5f64, -5f64, -5f64));
+        assert_eq!(b.max, TypedVec::point(5f64, 3f64, 5f64));
+    }
 }
@@ -0,0 +1,318 @@
+use crate::bounds::BoundingBox;
+use crate::group::{Groupable, Parent};
+use crate::hittable::{Hittable, HittableImpl};
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::matrix4::Matrix4;
+use crate::motion::Motion;
+use crate::ray::Ray;
+use crate::sync::Lock;
+use crate::transform::Transform;
+use crate::vec3::TypedVec;
+use anyhow::{anyhow, Result};
+use ray_trace_challenge_derive::Groupable;
+use std::fmt::{Debug, Formatter};
+
+#[derive(Clone, Copy, Debug, Default, PartialOrd, PartialEq)]
+pub enum Operation {
+    #[default]
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Two shapes, either of which may be a group or another CSG, combined.
+#[derive(Clone, Default, PartialOrd, PartialEq, Groupable)]
+pub struct Csg<'a> {
+    pub operation: Operation,
+    pub transform: Option<Transform>,
+    pub motion: Option<Motion>,
+    pub material: Material,
+    pub parent: Option<Parent<'a>>,
+    pub operands: Lock<Option<(&'a dyn Hittable, &'a dyn Hittable)>>,
+}
+
+impl<'a> Csg<'a> {
+    pub fn new(operation: Operation) -> Self {
+        Self {
+            operation,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_operands(&self, left: &'a dyn Hittable, right: &'a dyn Hittable) {
+        *self.operands.write() = Some((left, right));
+        if let Some(parent) = &self.parent {
+            parent.invalidate();
+        }
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Some(transform.into());
+    }
+
+    /// The list must already be sorted.
+    pub fn filter_intersections<'i>(&self, xs: Vec<Intersection<'i>>) -> Vec<Intersection<'i>> {
+        let left = match *self.operands.read() {
+            Some((left, _)) => left,
+            None => return vec![],
+        };
+
+        let mut inl = false;
+        let mut inr = false;
+        let mut result = vec![];
+        for i in xs {
+            let lhit = left.includes(i.obj);
+            if intersection_allowed(self.operation, lhit, inl, inr) {
+                result.push(i);
+            }
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+        result
+    }
+}
+
+pub fn intersection_allowed(op: Operation, lhit: bool, inl: bool, inr: bool) -> bool {
+    match op {
+        Operation::Union => (lhit && !inr) || (!lhit && !inl),
+        Operation::Intersection => (lhit && inr) || (!lhit && inl),
+        Operation::Difference => (lhit && !inr) || (!lhit && inl),
+    }
+}
+
+impl<'a> Debug for Csg<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Csg")
+            .field("operation", &self.operation)
+            .field("transform", &self.transform)
+            .field("material", &self.material)
+            .finish()
+    }
+}
+
+impl<'a> HittableImpl for Csg<'a> {
+    fn h_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
+        let (left, right) = match *self.operands.read() {
+            Some(operands) => operands,
+            None => return vec![],
+        };
+        if !self.bounds().intersects(ray) {
+            return vec![];
+        }
+        let mut xs = left.intersect(ray);
+        xs.extend(right.intersect(ray));
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        self.filter_intersections(xs)
+    }
+
+    fn h_normal_at(&self, _p: TypedVec) -> Result<TypedVec> {
+        Err(anyhow!(
+            "CSG shapes don't have normals; ask the operand that was hit"
+        ))
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        if let Some((left, right)) = *self.operands.read() {
+            bounds.merge(&left.parent_space_bounds());
+            bounds.merge(&right.parent_space_bounds());
+        }
+        bounds
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn transform(&self) -> &Option<Transform> {
+        &self.transform
+    }
+
+    fn parent(&self) -> Option<&dyn Hittable> {
+        self.parent.as_ref().map(|p| p.as_hittable())
+    }
+
+    fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+
+    fn includes(&self, other: &dyn Hittable) -> bool {
+        match *self.operands.read() {
+            Some((left, right)) => left.includes(other) || right.includes(other),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csg;
+    use crate::cube::Cube;
+    use crate::group;
+    use crate::group::Group;
+    use crate::sphere::Sphere;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_create() {
+        let c = Arc::new(Csg::new(Operation::Union));
+        let mut s1 = Sphere::new();
+        let mut s2 = Cube::default();
+        csg!(c, s1, s2);
+        let (left, right) = c.operands.read().unwrap();
+        assert!(std::ptr::eq(
+            left as *const dyn Hittable as *const (),
+            &s1 as *const Sphere as *const ()
+        ));
+        assert!(std::ptr::eq(
+            right as *const dyn Hittable as *const (),
+            &s2 as *const Cube as *const ()
+        ));
+        assert_eq!(s1.parent, Some(Parent::Csg(Arc::clone(&c))));
+        assert_eq!(s2.parent, Some(Parent::Csg(Arc::clone(&c))));
+    }
+
+    #[test]
+    fn test_intersection_allowed() {
+        use Operation::*;
+        let examples = vec![
+            (Union, true, true, true, false),
+            (Union, true, true, false, true),
+            (Union, true, false, true, false),
+            (Union, true, false, false, true),
+            (Union, false, true, true, false),
+            (Union, false, true, false, false),
+            (Union, false, false, true, true),
+            (Union, false, false, false, true),
+            (Intersection, true, true, true, true),
+            (Intersection, true, true, false, false),
+            (Intersection, true, false, true, true),
+            (Intersection, true, false, false, false),
+            (Intersection, false, true, true, true),
+            (Intersection, false, true, false, true),
+            (Intersection, false, false, true, false),
+            (Intersection, false, false, false, false),
+            (Difference, true, true, true, false),
+            (Difference, true, true, false, true),
+            (Difference, true, false, true, false),
+            (Difference, true, false, false, true),
+            (Difference, false, true, true, true),
+            (Difference, false, true, false, true),
+            (Difference, false, false, true, false),
+            (Difference, false, false, false, false),
+        ];
+        for (op, lhit, inl, inr, expected) in examples {
+            dbg!(&(op, lhit, inl, inr));
+            assert_eq!(intersection_allowed(op, lhit, inl, inr), expected);
+        }
+    }
+
+    #[test]
+    fn test_filter_intersections() {
+        let examples = vec![
+            (Operation::Union, 0, 3),
+            (Operation::Intersection, 1, 2),
+            (Operation::Difference, 0, 1),
+        ];
+        for (op, x0, x1) in examples {
+            dbg!(&op);
+            let c = Arc::new(Csg::new(op));
+            let mut s1 = Sphere::new();
+            let mut s2 = Cube::default();
+            csg!(c, s1, s2);
+            let xs = vec![
+                Intersection::new(1f64, &s1),
+                Intersection::new(2f64, &s2),
+                Intersection::new(3f64, &s1),
+                Intersection::new(4f64, &s2),
+            ];
+            let result = c.filter_intersections(xs.clone());
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0].t, xs[x0].t);
+            assert_eq!(result[1].t, xs[x1].t);
+        }
+    }
+
+    #[test]
+    fn test_ray_misses() {
+        let c = Arc::new(Csg::new(Operation::Union));
+        let mut s1 = Sphere::new();
+        let mut s2 = Cube::default();
+        csg!(c, s1, s2);
+        let r = Ray::new(
+            TypedVec::point(0f64, 2f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        assert!(c.intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_ray_hits() {
+        let c = Arc::new(Csg::new(Operation::Union));
+        let mut s1 = Sphere::new();
+        let mut s2 = Sphere::new();
+        s2.transform = Some(Matrix4::translation(0f64, 0f64, 0.5).into());
+        csg!(c, s1, s2);
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let xs = c.intersect(r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4f64);
+        assert_eq!(xs[0].obj, &s1 as &dyn Hittable);
+        assert_eq!(xs[1].t, 6.5);
+        assert_eq!(xs[1].obj, &s2 as &dyn Hittable);
+    }
+
+    #[test]
+    fn test_group_operand() {
+        // Carve a sphere out of a group holding a single cube; the ray should
+        // leave the cube's front face and then hit the far side of the hole.
+        let c = Arc::new(Csg::new(Operation::Difference));
+        let mut g = Group::new();
+        g.set_parent(&c);
+        let g = Arc::new(g);
+        let mut cube = Cube::default();
+        group!(g, cube);
+        let mut s = Sphere::new();
+        s.transform = Some(Matrix4::scaling(0.5, 0.5, 0.5).into());
+        s.set_parent(&c);
+        c.set_operands(&*g, &s);
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let xs = c.intersect(r);
+        assert_eq!(xs.len(), 4);
+        assert_eq!(
+            xs.iter().map(|i| i.t).collect::<Vec<f64>>(),
+            vec![4f64, 4.5, 5.5, 6f64]
+        );
+        assert_eq!(xs[0].obj, &cube as &dyn Hittable);
+        assert_eq!(xs[1].obj, &s as &dyn Hittable);
+    }
+
+    #[test]
+    fn test_no_normal() {
+        let c = Csg::new(Operation::Union);
+        assert!(c.normal_at(TypedVec::point(0f64, 0f64, 0f64)).is_err());
+    }
+
+    #[test]
+    fn test_bounds() {
+        let c = Arc::new(Csg::new(Operation::Difference));
+        let mut s1 = Sphere::new();
+        let mut s2 = Sphere::new();
+        s2.transform = Some(Matrix4::translation(2f64, 3f64, 4f64).into());
+        csg!(c, s1, s2);
+        let b = c.bounds();
+        assert_eq!(b.min, TypedVec::point(-1f64, -1f64, -1f64));
+        assert_eq!(b.max, TypedVec::point(3f64, 4f64, 5f64));
+    }
+}
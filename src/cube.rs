@@ -1,7 +1,7 @@
+use crate::bounds::BoundingBox;
 use crate::hittable::HittableImpl;
 use crate::intersection::Intersection;
 use crate::material::Material;
-use crate::matrix::Matrix;
 use crate::ray::Ray;
 use crate::vec3::TypedVec;
 use crate::{shape, EPSILON};
@@ -20,16 +20,16 @@ impl<'a> Cube<'a> {
             (tmin, tmax)
         }
     }
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
         let (xtmin, xtmax) = self.check_axis(ray.origin.x, ray.direction.x);
         let (ytmin, ytmax) = self.check_axis(ray.origin.y, ray.direction.y);
         let (ztmin, ztmax) = self.check_axis(ray.origin.z, ray.direction.z);
-        let mins = vec![xtmin, ytmin, ztmin];
+        let mins = [xtmin, ytmin, ztmin];
         let tmin = mins
             .iter()
             .max_by(|x, y| x.partial_cmp(y).unwrap())
             .unwrap();
-        let maxes = vec![xtmax, ytmax, ztmax];
+        let maxes = [xtmax, ytmax, ztmax];
         let tmax = maxes
             .iter()
             .min_by(|x, y| x.partial_cmp(y).unwrap())
@@ -44,7 +44,7 @@ impl<'a> Cube<'a> {
     }
 
     fn local_normal_at(&self, p: TypedVec) -> Result<TypedVec> {
-        let possibles = vec![p.x.abs(), p.y.abs(), p.z.abs()];
+        let possibles = [p.x.abs(), p.y.abs(), p.z.abs()];
         let maxc = possibles
             .iter()
             .max_by(|x, y| x.partial_cmp(y).unwrap())
@@ -57,6 +57,13 @@ impl<'a> Cube<'a> {
             Ok(TypedVec::vector(0f64, 0f64, p.z))
         }
     }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            TypedVec::point(-1f64, -1f64, -1f64),
+            TypedVec::point(1f64, 1f64, 1f64),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -236,4 +243,11 @@ mod test {
             assert_eq!(c.normal_at(point).unwrap(), normal);
         }
     }
+
+    #[test]
+    fn test_bounds() {
+        let b = Cube::default().bounds();
+        assert_eq!(b.min, TypedVec::point(-1f64, -1f64, -1f64));
+        assert_eq!(b.max, TypedVec::point(1f64, 1f64, 1f64));
+    }
 }
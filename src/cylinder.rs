@@ -1,22 +1,22 @@
+use crate::bounds::BoundingBox;
 use crate::hittable::{Hittable, HittableImpl};
 use crate::intersection::Intersection;
 use crate::material::Material;
-use crate::matrix::Matrix;
 use crate::ray::Ray;
 use crate::vec3::TypedVec;
 use crate::{shape, ZeroIsh, EPSILON};
 
 use anyhow::Result;
-use std::f64::INFINITY;
 
 shape!(Cylinder, nodefault, minimum -> f64, maximum -> f64, closed -> bool);
 
 impl<'a> Default for Cylinder<'a> {
     fn default() -> Self {
         Self {
-            minimum: -INFINITY,
-            maximum: INFINITY,
+            minimum: -f64::INFINITY,
+            maximum: f64::INFINITY,
             transform: None,
+            motion: None,
             material: Material::default(),
             closed: false,
             parent: None,
@@ -25,7 +25,7 @@ impl<'a> Default for Cylinder<'a> {
 }
 
 impl<'a> Cylinder<'a> {
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
         let a = ray.direction.x.powi(2) + ray.direction.z.powi(2);
         if a.zeroish() {
             return self.intersect_caps(ray);
@@ -62,10 +62,17 @@ impl<'a> Cylinder<'a> {
             Ok(TypedVec::vector(p.x, 0.0, p.z))
         }
     }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            TypedVec::point(-1.0, self.minimum, -1.0),
+            TypedVec::point(1.0, self.maximum, 1.0),
+        )
+    }
 }
 
 pub trait Capped {
-    fn intersect_caps(&self, ray: Ray) -> Vec<Intersection>
+    fn intersect_caps(&self, ray: Ray) -> Vec<Intersection<'_>>
     where
         Self: Hittable + std::marker::Sized,
     {
@@ -116,6 +123,7 @@ impl<'a> Capped for Cylinder<'a> {
 mod test {
     use crate::cylinder::Cylinder;
     use crate::hittable::Hittable;
+    use crate::hittable::HittableImpl;
     use crate::ray::Ray;
     use crate::roundf;
     use crate::vec3::TypedVec;
@@ -368,4 +376,20 @@ mod test {
             assert_eq!(c.local_normal_at(p).unwrap(), n)
         }
     }
+
+    #[test]
+    fn test_bounds() {
+        let b = Cylinder::default().bounds();
+        assert_eq!(b.min, TypedVec::point(-1f64, -f64::INFINITY, -1f64));
+        assert_eq!(b.max, TypedVec::point(1f64, f64::INFINITY, 1f64));
+
+        let c = Cylinder {
+            minimum: -5f64,
+            maximum: 3f64,
+            ..Default::default()
+        };
+        let b = c.bounds();
+        assert_eq!(b.min, TypedVec::point(-1f64, -5f64, -1f64));
+        assert_eq!(b.max, TypedVec::point(1f64, 3f64, 1f64));
+    }
 }
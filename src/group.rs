@@ -1,77 +1,365 @@
+use crate::bounds::BoundingBox;
+use crate::bvh::{Bvh, BvhStats};
+use crate::csg::Csg;
 use crate::hittable::{Hittable, HittableImpl};
 use crate::intersection::Intersection;
 use crate::material::Material;
-use crate::matrix::Matrix;
+use crate::matrix4::Matrix4;
+use crate::motion::Motion;
 use crate::ray::Ray;
+use crate::sync::Lock;
+use crate::transform::Transform;
 use crate::vec3::TypedVec;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use ray_trace_challenge_derive::Groupable;
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
 
+// Unused for now.
+#[allow(dead_code, unused_imports)]
 mod tree;
 
-#[derive(Clone, Debug, Default, PartialOrd, PartialEq, Groupable)]
+#[derive(Clone, Default, PartialOrd, PartialEq, Groupable)]
 pub struct Group<'a> {
-    pub transform: Option<Matrix<f64>>,
+    pub transform: Option<Transform>,
+    pub motion: Option<Motion>,
     pub material: Material,
-    pub parent: Option<Rc<RefCell<Group<'a>>>>,
-    pub children: Vec<&'a dyn Hittable>,
+    pub parent: Option<Parent<'a>>,
+    pub children: Lock<Vec<&'a dyn Hittable>>,
+    cached_bounds: Lock<Option<BoundingBox>>,
+    bvh: Lock<Option<Bvh<'a>>>,
+}
+
+#[derive(Clone, Debug, PartialOrd, PartialEq)]
+pub enum Parent<'a> {
+    Group(Arc<Group<'a>>),
+    Csg(Arc<Csg<'a>>),
+}
+
+impl<'a> Parent<'a> {
+    pub fn as_hittable(&self) -> &(dyn Hittable + 'a) {
+        match self {
+            Parent::Group(g) => &**g,
+            Parent::Csg(c) => &**c,
+        }
+    }
+
+    // Whatever the parent has cached covers its children, so it's stale as
+    // soon as one of them changes.
+    pub(crate) fn invalidate(&self) {
+        match self {
+            Parent::Group(g) => g.invalidate(),
+            Parent::Csg(c) => {
+                if let Some(parent) = &c.parent {
+                    parent.invalidate()
+                }
+            }
+        }
+    }
+}
+
+impl<'a> From<&Arc<Group<'a>>> for Parent<'a> {
+    fn from(group: &Arc<Group<'a>>) -> Self {
+        Parent::Group(Arc::clone(group))
+    }
+}
+
+impl<'a> From<&Arc<Csg<'a>>> for Parent<'a> {
+    fn from(csg: &Arc<Csg<'a>>) -> Self {
+        Parent::Csg(Arc::clone(csg))
+    }
 }
 
 pub trait Groupable<'a> {
-    fn set_parent(&mut self, parent: &Rc<RefCell<Group<'a>>>);
+    fn set_parent<P: Into<Parent<'a>>>(&mut self, parent: P);
 }
 
 impl<'a> Group<'a> {
-    pub fn set_child<T>(&'a mut self, child: &'a T)
-    where
-        T: Hittable,
-    {
-        self.children.push(child);
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    fn local_normal_at(&self, _: TypedVec) -> Result<TypedVec> {
-        unreachable!()
+    pub fn set_child(&self, child: &'a dyn Hittable) {
+        self.children.write().push(child);
+        self.invalidate();
+    }
+
+    fn invalidate(&self) {
+        self.cached_bounds.replace(None);
+        self.bvh.replace(None);
+        if let Some(parent) = &self.parent {
+            parent.invalidate();
+        }
+    }
+
+    /// Adding a child here or in any group below throws it away again.
+    pub fn build_bvh(&self) -> BvhStats {
+        let bvh = Bvh::new(&self.children.read());
+        let stats = bvh.stats();
+        self.bvh.replace(Some(bvh));
+        stats
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Some(transform.into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.children.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.read().is_empty()
+    }
+}
+
+// Children point back at their parent, so printing them from here would
+// recurse forever.
+impl<'a> Debug for Group<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Group")
+            .field("transform", &self.transform)
+            .field("material", &self.material)
+            .field("children", &self.len())
+            .finish()
     }
 }
 
 impl<'a> HittableImpl for Group<'a> {
-    fn h_intersect(&self, _ray: Ray) -> Vec<Intersection> {
-        unimplemented!()
+    fn h_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
+        if !self.bounds().intersects(ray) {
+            return vec![];
+        }
+        if let Some(bvh) = &*self.bvh.read() {
+            return bvh.intersect(ray);
+        }
+        let children: Vec<&'a dyn Hittable> = self.children.read().clone();
+        let mut xs: Vec<Intersection> = children
+            .into_iter()
+            .flat_map(|c| c.intersect(ray))
+            .collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        xs
     }
 
-    fn normal_at(&self, _p: TypedVec) -> Result<TypedVec> {
-        unimplemented!()
+    fn h_normal_at(&self, _p: TypedVec) -> Result<TypedVec> {
+        Err(anyhow!(
+            "groups don't have normals; ask the child that was hit"
+        ))
+    }
+
+    // Worked out on first use, since children can't be reached mutably once
+    // they've been added.
+    fn bounds(&self) -> BoundingBox {
+        if let Some(bounds) = self.cached_bounds.get() {
+            return bounds;
+        }
+        let mut bounds = BoundingBox::empty();
+        for c in self.children.read().iter() {
+            bounds.merge(&c.parent_space_bounds());
+        }
+        self.cached_bounds.replace(Some(bounds));
+        bounds
     }
 
     fn material(&self) -> &Material {
         &self.material
     }
 
-    fn transform(&self) -> &Option<Matrix<f64>> {
+    fn transform(&self) -> &Option<Transform> {
         &self.transform
     }
+
+    fn parent(&self) -> Option<&dyn Hittable> {
+        self.parent.as_ref().map(|p| p.as_hittable())
+    }
+
+    fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+
+    fn includes(&self, other: &dyn Hittable) -> bool {
+        self.children.read().iter().any(|c| c.includes(other))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    // use crate::group;
+    use crate::cylinder::Cylinder;
+    use crate::group;
+    use crate::matrix4::Matrix4;
     use crate::sphere::Sphere;
-    use std::cell::RefMut;
+
+    #[test]
+    fn test_empty_group() {
+        let g = Group::new();
+        assert!(g.is_empty());
+        assert_eq!(g.transform, None);
+    }
 
     #[test]
     fn test_add_child() {
-        let g = Rc::new(RefCell::new(Group::default()));
+        let g = Arc::new(Group::default());
         let mut s = Sphere::default();
-        // s.set_parent(&g);
-        // group!(p, s);
-        {
-            let mut mp: RefMut<_> = g.borrow_mut();
-            mp.set_child(&s);
-        }
-        s.set_parent(&g);
-        // assert_eq!(s.parent, Some(g))
+        group!(g, s);
+        assert_eq!(g.len(), 1);
+        assert_eq!(s.parent, Some(Parent::Group(Arc::clone(&g))));
+    }
+
+    #[test]
+    fn test_intersect_empty_group() {
+        let g = Group::new();
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        assert!(g.intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_intersect_nonempty_group() {
+        let g = Arc::new(Group::new());
+        let mut s1 = Sphere::new();
+        let mut s2 = Sphere::new();
+        s2.transform = Some(Matrix4::translation(0f64, 0f64, -3f64).into());
+        let mut s3 = Sphere::new();
+        s3.transform = Some(Matrix4::translation(5f64, 0f64, 0f64).into());
+        group!(g, s1);
+        group!(g, s2);
+        group!(g, s3);
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let xs = g.intersect(r);
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs[0].obj, &s2 as &dyn Hittable);
+        assert_eq!(xs[1].obj, &s2 as &dyn Hittable);
+        assert_eq!(xs[2].obj, &s1 as &dyn Hittable);
+        assert_eq!(xs[3].obj, &s1 as &dyn Hittable);
+    }
+
+    #[test]
+    fn test_intersect_transformed_group() {
+        let mut g = Group::new();
+        g.set_transform(Matrix4::scaling(2f64, 2f64, 2f64));
+        let g = Arc::new(g);
+        let mut s = Sphere::new();
+        s.transform = Some(Matrix4::translation(5f64, 0f64, 0f64).into());
+        group!(g, s);
+        let r = Ray::new(
+            TypedVec::point(10f64, 0f64, -10f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        assert_eq!(g.intersect(r).len(), 2);
+    }
+
+    #[test]
+    fn test_intersect_nested_group() {
+        let outer = Arc::new(Group::new());
+        let mut inner = Group::new();
+        inner.set_transform(Matrix4::translation(0f64, 0f64, 5f64));
+        inner.set_parent(&outer);
+        let inner = Arc::new(inner);
+        outer.set_child(&*inner);
+        let mut s = Sphere::new();
+        s.transform = Some(Matrix4::scaling(2f64, 2f64, 2f64).into());
+        group!(inner, s);
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let xs = outer.intersect(r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 8f64);
+        assert_eq!(xs[1].t, 12f64);
+    }
+
+    #[test]
+    fn test_nested_group_grows() {
+        let outer = Arc::new(Group::new());
+        let mut inner = Group::new();
+        inner.set_parent(&outer);
+        let inner = Arc::new(inner);
+        outer.set_child(&*inner);
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        assert!(outer.intersect(r).is_empty());
+        outer.build_bvh();
+
+        let mut s = Sphere::new();
+        group!(inner, s);
+        assert_eq!(inner.intersect(r).len(), 2);
+        assert_eq!(outer.intersect(r).len(), 2);
+    }
+
+    #[test]
+    fn test_group_has_no_normal() {
+        let g = Group::new();
+        assert!(g.normal_at(TypedVec::point(0f64, 0f64, 0f64)).is_err());
+    }
+
+    #[test]
+    fn test_group_bounds() {
+        let g = Arc::new(Group::new());
+        let mut s = Sphere::new();
+        s.transform = Some(
+            (Matrix4::translation(2f64, 5f64, -3f64) * Matrix4::scaling(2f64, 2f64, 2f64)).into(),
+        );
+        let mut c = Cylinder {
+            minimum: -2f64,
+            maximum: 2f64,
+            ..Default::default()
+        };
+        c.transform = Some(
+            (Matrix4::translation(-4f64, -1f64, 4f64) * Matrix4::scaling(0.5, 1f64, 0.5)).into(),
+        );
+        group!(g, s);
+        group!(g, c);
+        let b = g.bounds();
+        assert_eq!(b.min, TypedVec::point(-4.5, -3f64, -5f64));
+        assert_eq!(b.max, TypedVec::point(4f64, 7f64, 4.5));
+    }
+
+    #[test]
+    fn test_ray_misses_bounds() {
+        let g = Arc::new(Group::new());
+        let mut s = Sphere::new();
+        s.transform = Some(Matrix4::translation(5f64, 0f64, 0f64).into());
+        group!(g, s);
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        assert!(!g.bounds().intersects(r));
+        assert!(g.intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_intersect_with_bvh() {
+        let g = Arc::new(Group::new());
+        let mut spheres: Vec<Sphere> = (0..20)
+            .map(|i| {
+                let mut s = Sphere::new();
+                s.transform = Some(Matrix4::translation(0f64, 0f64, 3f64 * i as f64).into());
+                s
+            })
+            .collect();
+        spheres.iter_mut().for_each(|s| s.set_parent(&g));
+        spheres.iter().for_each(|s| g.set_child(s));
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let expected: Vec<f64> = g.intersect(r).iter().map(|i| i.t).collect();
+        let stats = g.build_bvh();
+        assert_eq!(stats.leaves, 8);
+        assert_eq!(stats.depth, 4);
+        let xs: Vec<f64> = g.intersect(r).iter().map(|i| i.t).collect();
+        assert_eq!(xs.len(), 40);
+        assert_eq!(xs, expected);
     }
 }
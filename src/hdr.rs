@@ -0,0 +1,252 @@
+use crate::colour::Colour;
+use anyhow::*;
+use std::io::{BufWriter, Write};
+
+// RGBE's shared exponent tops out at 2^127; anything brighter is clamped.
+const MAX_RGBE: f64 = 1e38;
+
+/// Negative values and NaN come out as zero.
+pub(crate) fn rgbe(colour: Colour) -> [u8; 4] {
+    let channel = |c: f64| {
+        if c.is_nan() {
+            0f64
+        } else {
+            c.clamp(0f64, MAX_RGBE)
+        }
+    };
+    let (r, g, b) = (
+        channel(colour.red),
+        channel(colour.green),
+        channel(colour.blue),
+    );
+    let brightest = r.max(g).max(b);
+    if brightest < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // brightest = mantissa * 2^exponent, with the mantissa in [0.5, 1)
+    let mut exponent = brightest.log2().floor() as i32 + 1;
+    let mut mantissa = brightest / 2f64.powi(exponent);
+    if mantissa >= 1f64 {
+        exponent += 1;
+        mantissa /= 2f64;
+    } else if mantissa < 0.5 {
+        exponent -= 1;
+        mantissa *= 2f64;
+    }
+    let scale = mantissa * 256f64 / brightest;
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+
+/// Rows are run-length encoded when the width allows it.
+pub(crate) fn encode_rgbe<W: Write>(
+    out: W,
+    width: usize,
+    height: usize,
+    pixels: &[Colour],
+) -> Result<()> {
+    ensure!(
+        pixels.len() == width * height,
+        "expected {} pixels, got {}",
+        width * height,
+        pixels.len()
+    );
+    let mut out = BufWriter::new(out);
+    write!(
+        out,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )?;
+
+    let mut line = Vec::with_capacity(width * 4);
+    let mut channel = Vec::with_capacity(width);
+    for row in pixels.chunks(width.max(1)) {
+        line.clear();
+        let encoded: Vec<[u8; 4]> = row.iter().map(|p| rgbe(*p)).collect();
+        // Only widths from 8 to 32767 can be run-length encoded; anything
+        // else is stored flat.
+        if !(8..=0x7fff).contains(&width) {
+            encoded.iter().for_each(|p| line.extend_from_slice(p));
+        } else {
+            line.extend_from_slice(&[2, 2, (width >> 8) as u8, width as u8]);
+            for c in 0..4 {
+                channel.clear();
+                channel.extend(encoded.iter().map(|p| p[c]));
+                run_length(&mut line, &channel);
+            }
+        }
+        out.write_all(&line)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// Runs shorter than this cost more to encode than they save.
+const MIN_RUN: usize = 4;
+
+fn run_length(out: &mut Vec<u8>, data: &[u8]) {
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut run_start = pos;
+        let mut run = 0;
+        while run_start < data.len() {
+            run = data[run_start..]
+                .iter()
+                .take(127)
+                .take_while(|b| **b == data[run_start])
+                .count();
+            if run >= MIN_RUN {
+                break;
+            }
+            run_start += run;
+        }
+        while pos < run_start {
+            let literal = (run_start - pos).min(128);
+            out.push(literal as u8);
+            out.extend_from_slice(&data[pos..pos + literal]);
+            pos += literal;
+        }
+        if run_start < data.len() {
+            out.push(128 + run as u8);
+            out.push(data[run_start]);
+            pos = run_start + run;
+        }
+    }
+}
+
+/// Little-endian, with rows stored bottom up.
+pub(crate) fn encode_pfm<W: Write>(
+    out: W,
+    width: usize,
+    height: usize,
+    pixels: &[Colour],
+) -> Result<()> {
+    ensure!(
+        pixels.len() == width * height,
+        "expected {} pixels, got {}",
+        width * height,
+        pixels.len()
+    );
+    let mut out = BufWriter::new(out);
+    write!(out, "PF\n{} {}\n-1.0\n", width, height)?;
+    for row in pixels.chunks(width.max(1)).rev() {
+        for p in row {
+            for c in [p.red, p.green, p.blue].iter() {
+                out.write_all(&(*c as f32).to_le_bytes())?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::colour::Colour;
+    use crate::hdr::{encode_pfm, encode_rgbe, rgbe};
+
+    #[test]
+    fn test_rgbe() {
+        let examples = vec![
+            ("black", Colour::new(0.0, 0.0, 0.0), [0, 0, 0, 0]),
+            ("white", Colour::new(1.0, 1.0, 1.0), [128, 128, 128, 129]),
+            ("dim", Colour::new(0.5, 0.25, 0.0), [128, 64, 0, 128]),
+            ("bright", Colour::new(12.0, 3.0, 0.75), [192, 48, 12, 132]),
+            (
+                "negative",
+                Colour::new(-1.0, 0.5, f64::NAN),
+                [0, 128, 0, 128],
+            ),
+        ];
+        for (name, colour, expected) in examples {
+            dbg!(&name);
+            assert_eq!(rgbe(colour), expected);
+        }
+    }
+
+    // Just enough of a reader to check what `encode_rgbe` wrote.
+    fn decode_rgbe(data: &[u8], width: usize, height: usize) -> Vec<[u8; 4]> {
+        let mut pos = 0;
+        let mut pixels = Vec::new();
+        for _ in 0..height {
+            if !(8..=0x7fff).contains(&width) {
+                pixels.extend(
+                    data[pos..pos + width * 4]
+                        .chunks(4)
+                        .map(|c| [c[0], c[1], c[2], c[3]]),
+                );
+                pos += width * 4;
+                continue;
+            }
+            assert_eq!(
+                &data[pos..pos + 4],
+                &[2, 2, (width >> 8) as u8, width as u8]
+            );
+            pos += 4;
+            let mut row = vec![[0u8; 4]; width];
+            for c in 0..4 {
+                let mut x = 0;
+                while x < width {
+                    let count = data[pos] as usize;
+                    if count > 128 {
+                        for p in row.iter_mut().skip(x).take(count - 128) {
+                            p[c] = data[pos + 1];
+                        }
+                        x += count - 128;
+                        pos += 2;
+                    } else {
+                        for (i, p) in row.iter_mut().skip(x).take(count).enumerate() {
+                            p[c] = data[pos + 1 + i];
+                        }
+                        x += count;
+                        pos += 1 + count;
+                    }
+                }
+            }
+            pixels.extend(row);
+        }
+        assert_eq!(pos, data.len());
+        pixels
+    }
+
+    #[test]
+    fn test_encode_rgbe() {
+        let examples = vec![("flat", 5, 2), ("runs", 300, 3)];
+        for (name, width, height) in examples {
+            dbg!(&name);
+            let pixels: Vec<Colour> = (0..width * height)
+                .map(|i| match (i / 7) % 3 {
+                    0 => Colour::new(0.25, 0.5, 0.75),
+                    1 => Colour::new(i as f64, 0.0, 1.0),
+                    _ => Colour::new(1.0, 2.0, (i % 5) as f64),
+                })
+                .collect();
+            let mut out = Vec::new();
+            encode_rgbe(&mut out, width, height, &pixels).unwrap();
+            let header = format!(
+                "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+                height, width
+            );
+            assert!(out.starts_with(header.as_bytes()));
+            let expected: Vec<_> = pixels.iter().map(|p| rgbe(*p)).collect();
+            assert_eq!(decode_rgbe(&out[header.len()..], width, height), expected);
+        }
+        assert!(encode_rgbe(Vec::new(), 2, 2, &[Colour::default()]).is_err());
+    }
+
+    #[test]
+    fn test_encode_pfm() {
+        let pixels = vec![Colour::new(1.0, 2.5, -3.0), Colour::new(100.0, 0.0, 0.125)];
+        let mut out = Vec::new();
+        encode_pfm(&mut out, 1, 2, &pixels).unwrap();
+        let mut expected = b"PF\n1 2\n-1.0\n".to_vec();
+        for c in [100f32, 0.0, 0.125, 1.0, 2.5, -3.0].iter() {
+            expected.extend_from_slice(&c.to_le_bytes());
+        }
+        assert_eq!(out, expected);
+    }
+}
@@ -1,27 +1,118 @@
+use crate::bounds::BoundingBox;
 use crate::colour::Colour;
 use crate::intersection::Intersection;
 use crate::material::Material;
-use crate::matrix::Matrix;
+use crate::motion::Motion;
 use crate::pattern::Pattern;
 use crate::ray::Ray;
+use crate::transform::Transform;
 use crate::vec3::TypedVec;
 use anyhow::Result;
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt::Debug;
 
-pub trait HittableImpl {
-    fn h_intersect(&self, ray: Ray) -> Vec<Intersection>;
-    fn normal_at(&self, p: TypedVec) -> Result<TypedVec>;
+/// Scenes are shared between render threads.
+pub trait HittableImpl: Send + Sync {
+    fn h_intersect(&self, ray: Ray) -> Vec<Intersection<'_>>;
+    fn h_normal_at(&self, p: TypedVec) -> Result<TypedVec>;
 
     fn material(&self) -> &Material;
-    fn transform(&self) -> &Option<Matrix<f64>>;
+    fn transform(&self) -> &Option<Transform>;
+    fn parent(&self) -> Option<&dyn Hittable>;
 
-    fn pattern_at(&self, pattern: &Pattern, point: TypedVec) -> Result<Colour> {
-        let object_point = if let Some(t) = self.transform() {
-            t.inverse()? * point
+    fn motion(&self) -> Option<&Motion> {
+        None
+    }
+
+    fn transform_at(&self, time: f64) -> Option<Cow<'_, Transform>> {
+        match self.motion() {
+            Some(motion) => Some(Cow::Owned(motion.at(time).into())),
+            None => self.transform().as_ref().map(Cow::Borrowed),
+        }
+    }
+
+    /// In object space.
+    fn bounds(&self) -> BoundingBox;
+
+    /// After the object's own transform, including any motion.
+    fn parent_space_bounds(&self) -> BoundingBox {
+        if let Some(motion) = self.motion() {
+            return motion.bounds(&self.bounds());
+        }
+        match self.transform() {
+            Some(t) => self.bounds().transform(t.matrix()),
+            None => self.bounds(),
+        }
+    }
+
+    /// Smooth triangles use where the hit landed.
+    fn h_normal_at_hit(&self, p: TypedVec, _hit: &Intersection) -> Result<TypedVec> {
+        self.h_normal_at(p)
+    }
+
+    fn normal_at(&self, p: TypedVec) -> Result<TypedVec> {
+        let local_point = self.world_to_object(p)?;
+        let local_normal = self.h_normal_at(local_point)?;
+        self.normal_to_world(local_normal)
+    }
+
+    fn normal_at_hit(&self, p: TypedVec, hit: &Intersection) -> Result<TypedVec> {
+        let local_point = self.world_to_object_at(p, hit.time)?;
+        let local_normal = self.h_normal_at_hit(local_point, hit)?;
+        self.normal_to_world_at(local_normal, hit.time)
+    }
+
+    fn world_to_object(&self, point: TypedVec) -> Result<TypedVec> {
+        self.world_to_object_at(point, 0f64)
+    }
+
+    fn world_to_object_at(&self, point: TypedVec, time: f64) -> Result<TypedVec> {
+        let point = if let Some(parent) = self.parent() {
+            parent.world_to_object_at(point, time)?
         } else {
             point
         };
+        if let Some(t) = self.transform_at(time) {
+            Ok(t.inverse()? * point)
+        } else {
+            Ok(point)
+        }
+    }
+
+    fn normal_to_world(&self, normal: TypedVec) -> Result<TypedVec> {
+        self.normal_to_world_at(normal, 0f64)
+    }
+
+    fn normal_to_world_at(&self, normal: TypedVec, time: f64) -> Result<TypedVec> {
+        let mut normal = if let Some(t) = self.transform_at(time) {
+            t.inverse_transpose()? * normal
+        } else {
+            normal
+        };
+        normal.w = 0f64;
+        let normal = normal.normalize();
+        if let Some(parent) = self.parent() {
+            parent.normal_to_world_at(normal, time)
+        } else {
+            Ok(normal)
+        }
+    }
+
+    /// Groups and CSG shapes search their children.
+    fn includes(&self, other: &dyn Hittable) -> bool {
+        std::ptr::eq(
+            self as *const Self as *const (),
+            other as *const dyn Hittable as *const (),
+        )
+    }
+
+    fn pattern_at(&self, pattern: &Pattern, point: TypedVec) -> Result<Colour> {
+        self.pattern_at_time(pattern, point, 0f64)
+    }
+
+    fn pattern_at_time(&self, pattern: &Pattern, point: TypedVec, time: f64) -> Result<Colour> {
+        let object_point = self.world_to_object_at(point, time)?;
         let world_point = if let Some(p) = pattern.transform() {
             p.inverse()? * object_point
         } else {
@@ -44,58 +135,248 @@ impl<'a, 'b> PartialOrd<dyn Hittable + 'b> for dyn Hittable + 'a {
 }
 
 pub trait Hittable: HittableImpl + Debug {
-    fn intersect(&self, ray: Ray) -> Vec<Intersection> {
-        let ray = if let Some(transform) = &self.transform() {
-            let t = transform.inverse().unwrap();
-            ray.transform(&t)
+    fn intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
+        let ray = if let Some(transform) = self.transform_at(ray.time) {
+            ray.transform(transform.inverse().unwrap())
         } else {
             ray
         };
-        self.h_intersect(ray)
+        let mut xs = self.h_intersect(ray);
+        for x in xs.iter_mut() {
+            x.time = ray.time;
+        }
+        xs
     }
 }
 
-impl<'a, T> Hittable for T where T: HittableImpl + Debug {}
+impl<T> Hittable for T where T: HittableImpl + Debug {}
 
-impl<'a, T> HittableImpl for &T
+impl<T> HittableImpl for &T
 where
     T: HittableImpl + Debug,
 {
-    fn h_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn h_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
         (*self).h_intersect(ray)
     }
 
-    fn normal_at(&self, p: TypedVec) -> Result<TypedVec> {
-        (*self).normal_at(p)
+    fn h_normal_at(&self, p: TypedVec) -> Result<TypedVec> {
+        (*self).h_normal_at(p)
+    }
+
+    fn h_normal_at_hit(&self, p: TypedVec, hit: &Intersection) -> Result<TypedVec> {
+        (*self).h_normal_at_hit(p, hit)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        (*self).bounds()
     }
 
     fn material(&self) -> &Material {
         (*self).material()
     }
 
-    fn transform(&self) -> &Option<Matrix<f64>> {
+    fn transform(&self) -> &Option<Transform> {
         (*self).transform()
     }
+
+    fn parent(&self) -> Option<&dyn Hittable> {
+        (*self).parent()
+    }
+
+    fn motion(&self) -> Option<&Motion> {
+        (*self).motion()
+    }
+
+    fn includes(&self, other: &dyn Hittable) -> bool {
+        (*self).includes(other)
+    }
 }
 
-#[allow(unconditional_recursion)]
-impl<'a, T> HittableImpl for &mut T
+impl<T> HittableImpl for &mut T
 where
     T: HittableImpl + Debug,
 {
-    fn h_intersect(&self, ray: Ray) -> Vec<Intersection> {
-        (*self).h_intersect(ray)
+    fn h_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
+        (**self).h_intersect(ray)
     }
 
-    fn normal_at(&self, p: TypedVec) -> Result<TypedVec> {
-        (*self).normal_at(p)
+    fn h_normal_at(&self, p: TypedVec) -> Result<TypedVec> {
+        (**self).h_normal_at(p)
+    }
+
+    fn h_normal_at_hit(&self, p: TypedVec, hit: &Intersection) -> Result<TypedVec> {
+        (**self).h_normal_at_hit(p, hit)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        (**self).bounds()
     }
 
     fn material(&self) -> &Material {
-        (*self).material()
+        (**self).material()
     }
 
-    fn transform(&self) -> &Option<Matrix<f64>> {
-        (*self).transform()
+    fn transform(&self) -> &Option<Transform> {
+        (**self).transform()
+    }
+
+    fn parent(&self) -> Option<&dyn Hittable> {
+        (**self).parent()
+    }
+
+    fn motion(&self) -> Option<&Motion> {
+        (**self).motion()
+    }
+
+    fn includes(&self, other: &dyn Hittable) -> bool {
+        (**self).includes(other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::colour::{BLACK, WHITE};
+    use crate::group;
+    use crate::group::{Group, Groupable};
+    use crate::hittable::{Hittable, HittableImpl};
+    use crate::intersection::Intersection;
+    use crate::matrix::Axis;
+    use crate::matrix4::Matrix4;
+    use crate::motion::Motion;
+    use crate::pattern::{Pattern, PatternType};
+    use crate::plane::Plane;
+    use crate::ray::Ray;
+    use crate::sphere::Sphere;
+    use crate::vec3::TypedVec;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    macro_rules! nested_groups {
+        ($g1:ident, $g2:ident) => {
+            let mut $g1 = Group::new();
+            $g1.set_transform(Matrix4::rotation(Axis::Y, PI / 2f64));
+            let $g1 = Arc::new($g1);
+            let mut $g2 = Group::new();
+            $g2.set_transform(Matrix4::scaling(1f64, 2f64, 3f64));
+            $g2.set_parent(&$g1);
+            let $g2 = Arc::new($g2);
+            $g1.set_child(&*$g2);
+        };
+    }
+
+    #[test]
+    fn test_world_to_object() {
+        let mut g1 = Group::new();
+        g1.set_transform(Matrix4::rotation(Axis::Y, PI / 2f64));
+        let g1 = Arc::new(g1);
+        let mut g2 = Group::new();
+        g2.set_transform(Matrix4::scaling(2f64, 2f64, 2f64));
+        g2.set_parent(&g1);
+        let g2 = Arc::new(g2);
+        g1.set_child(&*g2);
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(5f64, 0f64, 0f64));
+        group!(g2, s);
+        let p = s
+            .world_to_object(TypedVec::point(-2f64, 0f64, -10f64))
+            .unwrap();
+        assert_eq!(p.round(100000f64), TypedVec::point(0f64, 0f64, -1f64));
+    }
+
+    #[test]
+    fn test_normal_to_world() {
+        nested_groups!(g1, g2);
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(5f64, 0f64, 0f64));
+        group!(g2, s);
+        let v = 3f64.sqrt() / 3f64;
+        let n = s.normal_to_world(TypedVec::vector(v, v, v)).unwrap();
+        assert_eq!(n.round(10000f64), TypedVec::vector(0.2857, 0.4286, -0.8571));
+    }
+
+    #[test]
+    fn test_normal_on_child() {
+        nested_groups!(g1, g2);
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(5f64, 0f64, 0f64));
+        group!(g2, s);
+        let n = s
+            .normal_at(TypedVec::point(1.7321, 1.1547, -5.5774))
+            .unwrap();
+        assert_eq!(n.round(1000f64), TypedVec::vector(0.286, 0.429, -0.857));
+    }
+
+    #[test]
+    fn test_transformed_plane_normal() {
+        let p = Plane {
+            transform: Some(Matrix4::rotation(Axis::Z, PI / 2f64).into()),
+            ..Default::default()
+        };
+        let n = p.normal_at(TypedVec::point(0f64, 0f64, 0f64)).unwrap();
+        assert_eq!(n.round(100000f64), TypedVec::vector(-1f64, 0f64, 0f64));
+    }
+
+    #[test]
+    fn test_moving_object() {
+        let mut s = Sphere::new();
+        s.motion = Some(Motion::linear(
+            Matrix4::identity(),
+            Matrix4::translation(5f64, 0f64, 0f64),
+        ));
+        let examples = vec![
+            ("start, aimed at start", 0f64, 0f64, vec![4f64, 6f64]),
+            ("start, aimed at end", 0f64, 5f64, vec![]),
+            ("middle, aimed at start", 0.5, 0f64, vec![]),
+            ("middle, aimed at middle", 0.5, 2.5, vec![4f64, 6f64]),
+            ("end, aimed at end", 1f64, 5f64, vec![4f64, 6f64]),
+        ];
+        for (name, time, x, expected) in examples {
+            dbg!(&name);
+            let r = Ray::new(
+                TypedVec::point(x, 0f64, -5f64),
+                TypedVec::vector(0f64, 0f64, 1f64),
+            )
+            .with_time(time);
+            let xs = s.intersect(r);
+            assert_eq!(xs.iter().map(|i| i.t).collect::<Vec<f64>>(), expected);
+            assert!(xs.iter().all(|i| i.time == time));
+        }
+
+        let hit = Intersection {
+            time: 1f64,
+            ..Intersection::new(4f64, &s)
+        };
+        let n = s
+            .normal_at_hit(TypedVec::point(5f64, 1f64, 0f64), &hit)
+            .unwrap();
+        assert_eq!(n, TypedVec::vector(0f64, 1f64, 0f64));
+        assert_eq!(
+            s.world_to_object_at(TypedVec::point(5f64, 0f64, 0f64), 1f64)
+                .unwrap(),
+            TypedVec::point(0f64, 0f64, 0f64)
+        );
+
+        let b = s.parent_space_bounds();
+        assert_eq!(b.min, TypedVec::point(-1f64, -1f64, -1f64));
+        assert_eq!(b.max, TypedVec::point(6f64, 1f64, 1f64));
+    }
+
+    #[test]
+    fn test_pattern_moves_with_object() {
+        let mut s = Sphere::new();
+        s.motion = Some(Motion::Function(|t| Matrix4::translation(t, 0f64, 0f64)));
+        let p = Pattern::new(PatternType::Stripe, *WHITE, *BLACK, false);
+        let examples = vec![
+            (0f64, 0.5, *WHITE),
+            (0f64, 1.5, *BLACK),
+            (1f64, 1.5, *WHITE),
+        ];
+        for (time, x, expected) in examples {
+            dbg!(&(time, x));
+            let c = s
+                .pattern_at_time(&p, TypedVec::point(x, 0f64, 0f64), time)
+                .unwrap();
+            assert_eq!(c, expected);
+        }
     }
 }
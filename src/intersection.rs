@@ -17,6 +17,7 @@ pub struct PreComp<'a> {
     pub(crate) under_point: TypedVec,
     pub(crate) reflectv: TypedVec,
     t: f64,
+    pub(crate) time: f64,
     pub(crate) n1: f64,
     pub(crate) n2: f64,
 }
@@ -42,15 +43,35 @@ impl<'a> PreComp<'a> {
 pub struct Intersection<'a> {
     pub t: f64,
     pub obj: &'a dyn Hittable,
+    pub u: f64,
+    pub v: f64,
+    pub time: f64,
 }
 
 impl<'a> Intersection<'a> {
     pub fn new(t: f64, obj: &'a dyn Hittable) -> Self {
-        Intersection { t, obj }
+        Intersection {
+            t,
+            obj,
+            u: 0f64,
+            v: 0f64,
+            time: 0f64,
+        }
+    }
+
+    /// `u` and `v` are barycentric, relative to the first vertex.
+    pub fn with_uv(t: f64, obj: &'a dyn Hittable, u: f64, v: f64) -> Self {
+        Intersection {
+            t,
+            obj,
+            u,
+            v,
+            time: 0f64,
+        }
     }
-    pub fn precompute(&self, ray: Ray, xs: &Intersections) -> PreComp {
+    pub fn precompute(&self, ray: Ray, xs: &Intersections) -> PreComp<'a> {
         let point = ray.position(self.t);
-        let mut normalv = self.obj.normal_at(point).unwrap();
+        let mut normalv = self.obj.normal_at_hit(point, self).unwrap();
         let eyev = -ray.direction;
         let inside = if normalv.dot_product(eyev) < 0.0 {
             normalv = -normalv;
@@ -88,6 +109,7 @@ impl<'a> Intersection<'a> {
         }
         PreComp {
             t: self.t,
+            time: ray.time,
             obj: self.obj,
             point,
             eyev,
@@ -134,7 +156,7 @@ impl<'a> Intersections<'a> {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
-    pub fn hit(&mut self) -> Option<&Intersection> {
+    pub fn hit(&mut self) -> Option<&Intersection<'a>> {
         self.0.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
         self.0.iter().filter(|&x| x.t > 0f64).take(1).next()
@@ -180,7 +202,7 @@ impl<'a> Index<usize> for Intersections<'a> {
 mod tests {
     use crate::intersection;
     use crate::intersection::{Intersection, Intersections};
-    use crate::matrix::Matrix;
+    use crate::matrix4::Matrix4;
     use crate::plane::Plane;
     use crate::ray::Ray;
     use crate::sphere::Sphere;
@@ -283,7 +305,7 @@ mod tests {
             TypedVec::vector(0f64, 0f64, 1f64),
         );
         let mut s = Sphere::new();
-        s.transform = Option::from(Matrix::translation(0f64, 0f64, 1f64));
+        s.transform = Some(Matrix4::translation(0f64, 0f64, 1f64).into());
         let i = Intersection::new(5.0, &s);
         let xs = Intersections::from_iter(vec![i.clone()]);
         let comps = i.precompute(r, &xs);
@@ -310,13 +332,13 @@ mod tests {
     #[test]
     fn test_find_nx_at_intersections() {
         let mut a = Sphere::glass();
-        a.transform = Some(Matrix::scaling(2f64, 2f64, 2f64));
+        a.transform = Some(Matrix4::scaling(2f64, 2f64, 2f64).into());
         a.material.refractive_index = 1.5;
         let mut b = Sphere::glass();
-        b.transform = Some(Matrix::translation(0f64, 0f64, -0.25));
+        b.transform = Some(Matrix4::translation(0f64, 0f64, -0.25).into());
         b.material.refractive_index = 2.0;
         let mut c = Sphere::glass();
-        c.transform = Some(Matrix::translation(0f64, 0f64, 0.25));
+        c.transform = Some(Matrix4::translation(0f64, 0f64, 0.25).into());
         c.material.refractive_index = 2.5;
         let r = Ray::new(
             TypedVec::point(0f64, 0f64, -4f64),
@@ -331,7 +353,7 @@ mod tests {
             Intersection::new(6f64, &a),
         ];
         let xs = Intersections::from_iter(x);
-        for test in vec![
+        for test in [
             (0, 1.0, 1.5),
             (1, 1.5, 2.0),
             (2, 2.0, 2.5),
@@ -355,7 +377,7 @@ mod tests {
             TypedVec::vector(0f64, 0f64, 1f64),
         );
         let mut c = Sphere::glass();
-        c.transform = Some(Matrix::translation(0f64, 0f64, 1f64));
+        c.transform = Some(Matrix4::translation(0f64, 0f64, 1f64).into());
         let i = Intersection::new(5f64, &c);
         let xs = Intersections::from_iter(vec![i.clone()]);
         let comps = i.precompute(r, &xs);
@@ -1,19 +1,35 @@
+pub mod bounds;
+pub mod bvh;
 pub mod camera;
 pub mod canvas;
 pub mod colour;
 pub mod cone;
+pub mod csg;
 pub mod cube;
 pub mod cylinder;
 pub mod group;
+pub mod hdr;
 pub mod hittable;
 pub mod intersection;
 pub mod lighting;
 pub mod material;
 pub mod matrix;
+pub mod matrix4;
+pub mod motion;
+pub mod obj;
 pub mod pattern;
 pub mod plane;
+pub mod png;
+pub mod ppm;
 pub mod ray;
+pub mod rng;
+pub mod sampling;
+pub mod smooth_triangle;
 pub mod sphere;
+pub mod sync;
+pub mod tonemap;
+pub mod transform;
+pub mod triangle;
 pub mod vec3;
 pub mod world;
 
@@ -30,60 +46,82 @@ impl ZeroIsh for f64 {
 
 #[macro_export]
 macro_rules! shape {
-    (@real$name:ident, $($derive:meta):*, $($n:tt -> $t:ty)*) => {
-        use crate::group::Group;
-        // use ray_trace_challenge_derive::Groupable;
-        use crate::group::Groupable;
-        use std::cell::RefCell;
-        use std::rc::Rc;
+    (@real$name:ident, [$($extra:tt)*], $($derive:meta):*, $($n:tt -> $t:ty)*) => {
+        use $crate::group::{Groupable, Parent};
         #[derive($($derive,)*)]
         pub struct $name<'a> {
             pub material: Material,
-            pub parent: Option<Rc<RefCell<Group<'a>>>>,
-            pub transform: Option<Matrix<f64>>,
+            pub parent: Option<Parent<'a>>,
+            pub transform: Option<$crate::transform::Transform>,
+            pub motion: Option<$crate::motion::Motion>,
             $(
             pub $n: $t,
             )*
         }
 
         impl<'a> Groupable<'a> for $name<'a> {
-            fn set_parent(&mut self, parent: &Rc<RefCell<Group<'a>>>) {
-                let parent = Rc::clone(parent);
-                self.parent = Some(parent);
+            fn set_parent<P: Into<Parent<'a>>>(&mut self, parent: P) {
+                self.parent = Some(parent.into());
             }
         }
 
         impl<'a> HittableImpl for $name<'a> {
-            fn h_intersect(&self, ray: Ray) -> Vec<Intersection> {
+            fn h_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
                 self.local_intersect(ray)
             }
-            fn normal_at(&self, p: TypedVec) -> Result<TypedVec> {
+            fn h_normal_at(&self, p: TypedVec) -> Result<TypedVec> {
                 self.local_normal_at(p)
             }
+            fn bounds(&self) -> $crate::bounds::BoundingBox {
+                self.local_bounds()
+            }
             fn material(&self) -> &Material {
                 &self.material
             }
-            fn transform(&self) -> &Option<Matrix<f64>> {
+            fn transform(&self) -> &Option<$crate::transform::Transform> {
                 &self.transform
             }
+            fn parent(&self) -> Option<&dyn $crate::hittable::Hittable> {
+                self.parent.as_ref().map(|p| p.as_hittable())
+            }
+            fn motion(&self) -> Option<&$crate::motion::Motion> {
+                self.motion.as_ref()
+            }
+            $($extra)*
         }
     };
+    ($name:ident, uv, $($n:tt -> $t:ty),*) => {
+        shape!(@real $name, [
+            fn h_normal_at_hit(&self, p: TypedVec, hit: &Intersection) -> Result<TypedVec> {
+                self.local_normal_at_hit(p, hit)
+            }
+        ], Clone:Debug:PartialOrd:PartialEq, $($n -> $t)*);
+    };
     ($name:ident, nodefault, $($n:tt -> $t:ty),*) => {
-        shape!(@real $name, Clone:Debug:PartialOrd:PartialEq, $($n -> $t)*);
+        shape!(@real $name, [], Clone:Debug:PartialOrd:PartialEq, $($n -> $t)*);
     };
     ($name:ident, $($n:tt -> $t:ty),*) => {
-        shape!(@real $name, Clone:Debug:Default:PartialOrd:PartialEq, $($n -> $t)*);
+        shape!(@real $name, [], Clone:Debug:Default:PartialOrd:PartialEq, $($n -> $t)*);
     };
     ($name:ident) => {
-        shape!(@real $name, Clone:Debug:Default:PartialOrd:PartialEq,);
+        shape!(@real $name, [], Clone:Debug:Default:PartialOrd:PartialEq,);
     };
 }
 
 #[macro_export]
 macro_rules! group {
     ($parent:ident, $child:ident) => {
-        $parent.borrow_mut().set_child(&$child);
         $child.set_parent(&$parent);
+        $parent.set_child(&$child);
+    };
+}
+
+#[macro_export]
+macro_rules! csg {
+    ($csg:ident, $left:ident, $right:ident) => {
+        $left.set_parent(&$csg);
+        $right.set_parent(&$csg);
+        $csg.set_operands(&$left, &$right);
     };
 }
 
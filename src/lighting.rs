@@ -1,10 +1,39 @@
 use crate::colour::Colour;
+use crate::rng::Rng;
 use crate::vec3::TypedVec;
 
+/// Only the diffuse and specular terms are attenuated.
+#[derive(Copy, Clone, Default, PartialEq, Debug, PartialOrd)]
+pub enum Attenuation {
+    #[default]
+    None,
+    InverseSquare,
+    Polynomial {
+        constant: f64,
+        linear: f64,
+        quadratic: f64,
+    },
+}
+
+impl Attenuation {
+    pub fn factor(&self, distance: f64) -> f64 {
+        match self {
+            Attenuation::None => 1f64,
+            Attenuation::InverseSquare => 1f64 / distance.powi(2),
+            Attenuation::Polynomial {
+                constant,
+                linear,
+                quadratic,
+            } => 1f64 / (constant + linear * distance + quadratic * distance.powi(2)),
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug, PartialOrd)]
 pub struct Point {
     pub(crate) intensity: Colour,
     pub(crate) position: TypedVec,
+    pub(crate) attenuation: Attenuation,
 }
 
 impl Point {
@@ -12,6 +41,689 @@ impl Point {
         Point {
             intensity,
             position,
+            attenuation: Attenuation::None,
+        }
+    }
+
+    pub fn position(&self) -> TypedVec {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: TypedVec) {
+        self.position = position;
+    }
+
+    pub fn intensity(&self) -> Colour {
+        self.intensity
+    }
+
+    pub fn set_intensity(&mut self, intensity: Colour) {
+        self.intensity = intensity;
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+}
+
+/// A rectangle sampled once per cell, at the middle unless jittered.
+#[derive(Copy, Clone, PartialEq, Debug, PartialOrd)]
+pub struct AreaLight {
+    pub(crate) corner: TypedVec,
+    pub(crate) uvec: TypedVec,
+    pub(crate) usteps: usize,
+    pub(crate) vvec: TypedVec,
+    pub(crate) vsteps: usize,
+    pub(crate) intensity: Colour,
+    pub(crate) jitter: bool,
+    pub(crate) attenuation: Attenuation,
+}
+
+impl AreaLight {
+    pub fn new(
+        corner: TypedVec,
+        full_uvec: TypedVec,
+        usteps: usize,
+        full_vvec: TypedVec,
+        vsteps: usize,
+        intensity: Colour,
+    ) -> Self {
+        assert!(
+            usteps > 0 && vsteps > 0,
+            "an area light needs at least one cell"
+        );
+        AreaLight {
+            corner,
+            uvec: full_uvec / usteps as f64,
+            usteps,
+            vvec: full_vvec / vsteps as f64,
+            vsteps,
+            intensity,
+            jitter: false,
+            attenuation: Attenuation::None,
+        }
+    }
+
+    pub fn corner(&self) -> TypedVec {
+        self.corner
+    }
+
+    pub fn set_corner(&mut self, corner: TypedVec) {
+        self.corner = corner;
+    }
+
+    /// The whole edge, not one cell's.
+    pub fn uvec(&self) -> TypedVec {
+        self.uvec * self.usteps as f64
+    }
+
+    pub fn set_uvec(&mut self, full_uvec: TypedVec) {
+        self.uvec = full_uvec / self.usteps as f64;
+    }
+
+    /// The whole edge, not one cell's.
+    pub fn vvec(&self) -> TypedVec {
+        self.vvec * self.vsteps as f64
+    }
+
+    pub fn set_vvec(&mut self, full_vvec: TypedVec) {
+        self.vvec = full_vvec / self.vsteps as f64;
+    }
+
+    pub fn usteps(&self) -> usize {
+        self.usteps
+    }
+
+    pub fn set_usteps(&mut self, usteps: usize) {
+        assert!(usteps > 0, "an area light needs at least one cell");
+        self.uvec = self.uvec * self.usteps as f64 / usteps as f64;
+        self.usteps = usteps;
+    }
+
+    pub fn vsteps(&self) -> usize {
+        self.vsteps
+    }
+
+    pub fn set_vsteps(&mut self, vsteps: usize) {
+        assert!(vsteps > 0, "an area light needs at least one cell");
+        self.vvec = self.vvec * self.vsteps as f64 / vsteps as f64;
+        self.vsteps = vsteps;
+    }
+
+    pub fn jitter(&self) -> bool {
+        self.jitter
+    }
+
+    pub fn set_jitter(&mut self, jitter: bool) {
+        self.jitter = jitter;
+    }
+
+    pub fn intensity(&self) -> Colour {
+        self.intensity
+    }
+
+    pub fn set_intensity(&mut self, intensity: Colour) {
+        self.intensity = intensity;
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+
+    pub fn samples(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    /// The middle of the light.
+    pub fn position(&self) -> TypedVec {
+        self.corner
+            + self.uvec * (self.usteps as f64 / 2f64)
+            + self.vvec * (self.vsteps as f64 / 2f64)
+    }
+
+    /// Jitter is keyed on `lit` so shading and shadows agree.
+    pub fn point_on_light(&self, u: usize, v: usize, lit: TypedVec) -> TypedVec {
+        let (ju, jv) = if self.jitter {
+            let mut rng = Rng::from_keys(&[
+                lit.x.to_bits(),
+                lit.y.to_bits(),
+                lit.z.to_bits(),
+                (u * self.vsteps + v) as u64,
+            ]);
+            (rng.next_f64(), rng.next_f64())
+        } else {
+            (0.5, 0.5)
+        };
+        self.corner + self.uvec * (u as f64 + ju) + self.vvec * (v as f64 + jv)
+    }
+}
+
+/// A point light limited to a cone; angles are from the axis, in radians.
+#[derive(Copy, Clone, PartialEq, Debug, PartialOrd)]
+pub struct SpotLight {
+    pub(crate) position: TypedVec,
+    pub(crate) direction: TypedVec,
+    pub(crate) inner: f64,
+    pub(crate) outer: f64,
+    pub(crate) falloff: f64,
+    pub(crate) intensity: Colour,
+    pub(crate) attenuation: Attenuation,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: TypedVec,
+        direction: TypedVec,
+        inner: f64,
+        outer: f64,
+        intensity: Colour,
+    ) -> Self {
+        SpotLight {
+            position,
+            direction: direction.normalize(),
+            inner,
+            outer,
+            falloff: 1f64,
+            intensity,
+            attenuation: Attenuation::None,
+        }
+    }
+
+    pub fn position(&self) -> TypedVec {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: TypedVec) {
+        self.position = position;
+    }
+
+    pub fn direction(&self) -> TypedVec {
+        self.direction
+    }
+
+    pub fn set_direction(&mut self, direction: TypedVec) {
+        self.direction = direction.normalize();
+    }
+
+    pub fn inner(&self) -> f64 {
+        self.inner
+    }
+
+    pub fn set_inner(&mut self, inner: f64) {
+        self.inner = inner;
+    }
+
+    pub fn outer(&self) -> f64 {
+        self.outer
+    }
+
+    pub fn set_outer(&mut self, outer: f64) {
+        self.outer = outer;
+    }
+
+    pub fn falloff(&self) -> f64 {
+        self.falloff
+    }
+
+    pub fn set_falloff(&mut self, falloff: f64) {
+        self.falloff = falloff;
+    }
+
+    pub fn intensity(&self) -> Colour {
+        self.intensity
+    }
+
+    pub fn set_intensity(&mut self, intensity: Colour) {
+        self.intensity = intensity;
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+
+    pub fn cone_factor(&self, point: TypedVec) -> f64 {
+        let cos = (point - self.position)
+            .normalize()
+            .dot_product(self.direction);
+        let (cos_inner, cos_outer) = (self.inner.cos(), self.outer.cos());
+        if cos >= cos_inner {
+            1f64
+        } else if cos <= cos_outer {
+            0f64
+        } else {
+            ((cos - cos_outer) / (cos_inner - cos_outer)).powf(self.falloff)
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, PartialOrd)]
+pub struct DirectionalLight {
+    pub(crate) direction: TypedVec,
+    pub(crate) intensity: Colour,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: TypedVec, intensity: Colour) -> Self {
+        DirectionalLight {
+            direction: direction.normalize(),
+            intensity,
+        }
+    }
+
+    pub fn direction(&self) -> TypedVec {
+        self.direction
+    }
+
+    pub fn set_direction(&mut self, direction: TypedVec) {
+        self.direction = direction.normalize();
+    }
+
+    pub fn intensity(&self) -> Colour {
+        self.intensity
+    }
+
+    pub fn set_intensity(&mut self, intensity: Colour) {
+        self.intensity = intensity;
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, PartialOrd)]
+pub struct LightSample {
+    /// Towards the light.
+    pub direction: TypedVec,
+    /// Infinite for directional lights.
+    pub distance: f64,
+    pub intensity: Colour,
+}
+
+impl LightSample {
+    fn towards(
+        position: TypedVec,
+        point: TypedVec,
+        intensity: Colour,
+        attenuation: Attenuation,
+    ) -> Self {
+        let v = position - point;
+        let distance = v.magnitude();
+        let intensity = match attenuation {
+            Attenuation::None => intensity,
+            a => intensity * a.factor(distance),
+        };
+        LightSample {
+            direction: v.normalize(),
+            distance,
+            intensity,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, PartialOrd)]
+pub enum Light {
+    Point(Point),
+    Area(AreaLight),
+    Spot(SpotLight),
+    Directional(DirectionalLight),
+}
+
+impl Light {
+    pub fn intensity(&self) -> Colour {
+        match self {
+            Light::Point(p) => p.intensity,
+            Light::Area(a) => a.intensity,
+            Light::Spot(s) => s.intensity,
+            Light::Directional(d) => d.intensity,
+        }
+    }
+
+    /// Once per cell for area lights, otherwise once.
+    pub fn samples(&self, point: TypedVec) -> Vec<LightSample> {
+        match self {
+            Light::Point(p) => vec![LightSample::towards(
+                p.position,
+                point,
+                p.intensity,
+                p.attenuation,
+            )],
+            Light::Area(a) => (0..a.vsteps)
+                .flat_map(|v| {
+                    (0..a.usteps).map(move |u| {
+                        LightSample::towards(
+                            a.point_on_light(u, v, point),
+                            point,
+                            a.intensity,
+                            a.attenuation,
+                        )
+                    })
+                })
+                .collect(),
+            Light::Spot(s) => vec![LightSample::towards(
+                s.position,
+                point,
+                s.intensity * s.cone_factor(point),
+                s.attenuation,
+            )],
+            Light::Directional(d) => vec![LightSample {
+                direction: -d.direction,
+                distance: f64::INFINITY,
+                intensity: d.intensity,
+            }],
         }
     }
 }
+
+impl From<Point> for Light {
+    fn from(p: Point) -> Self {
+        Light::Point(p)
+    }
+}
+
+impl From<AreaLight> for Light {
+    fn from(a: AreaLight) -> Self {
+        Light::Area(a)
+    }
+}
+
+impl From<SpotLight> for Light {
+    fn from(s: SpotLight) -> Self {
+        Light::Spot(s)
+    }
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(d: DirectionalLight) -> Self {
+        Light::Directional(d)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::colour::{Colour, WHITE};
+    use crate::lighting::{AreaLight, Attenuation, DirectionalLight, Light, Point, SpotLight};
+    use crate::vec3::TypedVec;
+    use std::f64::consts::PI;
+
+    fn light() -> AreaLight {
+        AreaLight::new(
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::vector(2f64, 0f64, 0f64),
+            4,
+            TypedVec::vector(0f64, 0f64, 1f64),
+            2,
+            *WHITE,
+        )
+    }
+
+    #[test]
+    fn test_area_light() {
+        let l = light();
+        assert_eq!(l.uvec, TypedVec::vector(0.5, 0f64, 0f64));
+        assert_eq!(l.vvec, TypedVec::vector(0f64, 0f64, 0.5));
+        assert_eq!(l.samples(), 8);
+        assert_eq!(l.position(), TypedVec::point(1f64, 0f64, 0.5));
+    }
+
+    #[test]
+    fn test_point_on_light() {
+        let l = light();
+        let lit = TypedVec::point(0f64, 0f64, 0f64);
+        let examples = vec![
+            (0, 0, TypedVec::point(0.25, 0f64, 0.25)),
+            (1, 0, TypedVec::point(0.75, 0f64, 0.25)),
+            (0, 1, TypedVec::point(0.25, 0f64, 0.75)),
+            (2, 0, TypedVec::point(1.25, 0f64, 0.25)),
+            (3, 1, TypedVec::point(1.75, 0f64, 0.75)),
+        ];
+        for (u, v, expected) in examples {
+            dbg!(&(u, v));
+            assert_eq!(l.point_on_light(u, v, lit), expected);
+        }
+    }
+
+    #[test]
+    fn test_jittered_point_stays_in_cell() {
+        let mut l = light();
+        l.set_jitter(true);
+        let lit = TypedVec::point(3f64, 1f64, -2f64);
+        for u in 0..4 {
+            for v in 0..2 {
+                let p = l.point_on_light(u, v, lit);
+                assert_eq!(p, l.point_on_light(u, v, lit));
+                assert!(p.x >= u as f64 * 0.5 && p.x < (u + 1) as f64 * 0.5);
+                assert!(p.z >= v as f64 * 0.5 && p.z < (v + 1) as f64 * 0.5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_light_samples() {
+        let lit = TypedVec::point(0f64, 0f64, 0f64);
+        assert_eq!(Light::from(light()).samples(lit).len(), 8);
+        let p = Point::new(TypedVec::point(1f64, 2f64, 3f64), *WHITE);
+        let samples = Light::from(p).samples(lit);
+        assert_eq!(samples.len(), 1);
+        assert_eq!(
+            samples[0].direction,
+            TypedVec::vector(1f64, 2f64, 3f64).normalize()
+        );
+        assert_eq!(samples[0].distance, 14f64.sqrt());
+        assert_eq!(samples[0].intensity, *WHITE);
+    }
+
+    #[test]
+    fn test_spot_cone_factor() {
+        let mut s = SpotLight::new(
+            TypedVec::point(0f64, 10f64, 0f64),
+            TypedVec::vector(0f64, -1f64, 0f64),
+            PI / 8f64,
+            PI / 4f64,
+            *WHITE,
+        );
+        let examples = vec![
+            ("on axis", TypedVec::point(0f64, 0f64, 0f64), 1f64),
+            ("inner cone", TypedVec::point(2f64, 0f64, 0f64), 1f64),
+            ("outside", TypedVec::point(10f64, 0f64, 0f64), 0f64),
+            ("behind", TypedVec::point(0f64, 20f64, 0f64), 0f64),
+            ("halfway", TypedVec::point(7.09714, 0f64, 0f64), 0.5),
+        ];
+        for (name, p, expected) in examples {
+            dbg!(&name);
+            assert_eq!(crate::roundf(s.cone_factor(p), 1000f64), expected);
+        }
+        s.set_falloff(2f64);
+        assert_eq!(
+            crate::roundf(s.cone_factor(TypedVec::point(7.09714, 0f64, 0f64)), 1000f64),
+            0.25
+        );
+    }
+
+    #[test]
+    fn test_directional_samples() {
+        let d = DirectionalLight::new(TypedVec::vector(0f64, -2f64, 0f64), *WHITE);
+        for p in [
+            TypedVec::point(0f64, 0f64, 0f64),
+            TypedVec::point(100f64, -5f64, 3f64),
+        ] {
+            let samples = Light::from(d).samples(p);
+            assert_eq!(samples.len(), 1);
+            assert_eq!(samples[0].direction, TypedVec::vector(0f64, 1f64, 0f64));
+            assert_eq!(samples[0].distance, f64::INFINITY);
+        }
+    }
+
+    #[test]
+    fn test_point_accessors() {
+        let mut p = Point::new(TypedVec::point(1f64, 2f64, 3f64), *WHITE);
+        assert_eq!(p.position(), TypedVec::point(1f64, 2f64, 3f64));
+        assert_eq!(p.intensity(), *WHITE);
+        assert_eq!(p.attenuation(), Attenuation::None);
+        p.set_position(TypedVec::point(0f64, 0f64, 0f64));
+        p.set_intensity(Colour::new(0.5, 0.5, 0.5));
+        p.set_attenuation(Attenuation::InverseSquare);
+        assert_eq!(p.position(), TypedVec::point(0f64, 0f64, 0f64));
+        assert_eq!(p.intensity(), Colour::new(0.5, 0.5, 0.5));
+        assert_eq!(p.attenuation(), Attenuation::InverseSquare);
+    }
+
+    #[test]
+    fn test_area_light_accessors() {
+        let mut l = light();
+        assert_eq!(l.corner(), TypedVec::point(0f64, 0f64, 0f64));
+        assert_eq!(l.uvec(), TypedVec::vector(2f64, 0f64, 0f64));
+        assert_eq!(l.vvec(), TypedVec::vector(0f64, 0f64, 1f64));
+        assert_eq!((l.usteps(), l.vsteps()), (4, 2));
+        assert!(!l.jitter());
+        assert_eq!(l.intensity(), *WHITE);
+        assert_eq!(l.attenuation(), Attenuation::None);
+        l.set_usteps(2);
+        l.set_vsteps(4);
+        assert_eq!(l.uvec(), TypedVec::vector(2f64, 0f64, 0f64));
+        assert_eq!(l.uvec, TypedVec::vector(1f64, 0f64, 0f64));
+        assert_eq!(l.vvec, TypedVec::vector(0f64, 0f64, 0.25));
+        l.set_corner(TypedVec::point(1f64, 1f64, 1f64));
+        l.set_uvec(TypedVec::vector(4f64, 0f64, 0f64));
+        l.set_vvec(TypedVec::vector(0f64, 2f64, 0f64));
+        l.set_jitter(true);
+        l.set_intensity(Colour::new(0.5, 0.5, 0.5));
+        l.set_attenuation(Attenuation::InverseSquare);
+        assert_eq!(l.corner(), TypedVec::point(1f64, 1f64, 1f64));
+        assert_eq!(l.uvec, TypedVec::vector(2f64, 0f64, 0f64));
+        assert_eq!(l.vvec(), TypedVec::vector(0f64, 2f64, 0f64));
+        assert_eq!(l.samples(), 8);
+        assert!(l.jitter());
+        assert_eq!(l.intensity(), Colour::new(0.5, 0.5, 0.5));
+        assert_eq!(l.attenuation(), Attenuation::InverseSquare);
+    }
+
+    #[test]
+    fn test_area_light_rejects_zero_steps() {
+        let examples: Vec<(&str, fn())> = vec![
+            ("new usteps", || {
+                AreaLight::new(
+                    TypedVec::point(0f64, 0f64, 0f64),
+                    TypedVec::vector(2f64, 0f64, 0f64),
+                    0,
+                    TypedVec::vector(0f64, 0f64, 1f64),
+                    2,
+                    *WHITE,
+                );
+            }),
+            ("new vsteps", || {
+                AreaLight::new(
+                    TypedVec::point(0f64, 0f64, 0f64),
+                    TypedVec::vector(2f64, 0f64, 0f64),
+                    4,
+                    TypedVec::vector(0f64, 0f64, 1f64),
+                    0,
+                    *WHITE,
+                );
+            }),
+            ("set_usteps", || light().set_usteps(0)),
+            ("set_vsteps", || light().set_vsteps(0)),
+        ];
+        for (name, f) in examples {
+            dbg!(&name);
+            assert!(std::panic::catch_unwind(f).is_err());
+        }
+        let mut l = light();
+        let rejected = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| l.set_usteps(0)));
+        assert!(rejected.is_err());
+        l.set_usteps(2);
+        assert_eq!(l.uvec(), TypedVec::vector(2f64, 0f64, 0f64));
+    }
+
+    #[test]
+    fn test_spot_accessors() {
+        let mut s = SpotLight::new(
+            TypedVec::point(0f64, 10f64, 0f64),
+            TypedVec::vector(0f64, -2f64, 0f64),
+            PI / 8f64,
+            PI / 4f64,
+            *WHITE,
+        );
+        assert_eq!(s.position(), TypedVec::point(0f64, 10f64, 0f64));
+        assert_eq!(s.direction(), TypedVec::vector(0f64, -1f64, 0f64));
+        assert_eq!(
+            (s.inner(), s.outer(), s.falloff()),
+            (PI / 8f64, PI / 4f64, 1f64)
+        );
+        assert_eq!(s.intensity(), *WHITE);
+        assert_eq!(s.attenuation(), Attenuation::None);
+        s.set_position(TypedVec::point(1f64, 2f64, 3f64));
+        s.set_direction(TypedVec::vector(3f64, 0f64, 0f64));
+        s.set_inner(0.1);
+        s.set_outer(0.2);
+        s.set_falloff(2f64);
+        s.set_intensity(Colour::new(0.5, 0.5, 0.5));
+        s.set_attenuation(Attenuation::InverseSquare);
+        assert_eq!(s.position(), TypedVec::point(1f64, 2f64, 3f64));
+        assert_eq!(s.direction(), TypedVec::vector(1f64, 0f64, 0f64));
+        assert_eq!((s.inner(), s.outer(), s.falloff()), (0.1, 0.2, 2f64));
+        assert_eq!(s.intensity(), Colour::new(0.5, 0.5, 0.5));
+        assert_eq!(s.attenuation(), Attenuation::InverseSquare);
+    }
+
+    #[test]
+    fn test_directional_accessors() {
+        let mut d = DirectionalLight::new(TypedVec::vector(0f64, -2f64, 0f64), *WHITE);
+        assert_eq!(d.direction(), TypedVec::vector(0f64, -1f64, 0f64));
+        assert_eq!(d.intensity(), *WHITE);
+        d.set_direction(TypedVec::vector(0f64, 0f64, 5f64));
+        d.set_intensity(Colour::new(0.5, 0.5, 0.5));
+        assert_eq!(d.direction(), TypedVec::vector(0f64, 0f64, 1f64));
+        assert_eq!(d.intensity(), Colour::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_attenuation_factor() {
+        let examples = vec![
+            ("none", Attenuation::None, 1f64),
+            ("inverse square", Attenuation::InverseSquare, 0.0625),
+            (
+                "constant",
+                Attenuation::Polynomial {
+                    constant: 2f64,
+                    linear: 0f64,
+                    quadratic: 0f64,
+                },
+                0.5,
+            ),
+            (
+                "mixed",
+                Attenuation::Polynomial {
+                    constant: 1f64,
+                    linear: 0.5,
+                    quadratic: 0.3125,
+                },
+                0.125,
+            ),
+        ];
+        for (name, a, expected) in examples {
+            dbg!(&name);
+            assert_eq!(a.factor(4f64), expected);
+        }
+    }
+
+    #[test]
+    fn test_attenuated_samples() {
+        let mut p = Point::new(TypedVec::point(0f64, 2f64, 0f64), *WHITE);
+        p.set_attenuation(Attenuation::InverseSquare);
+        let samples = Light::from(p).samples(TypedVec::point(0f64, 0f64, 0f64));
+        assert_eq!(samples[0].intensity, Colour::new(0.25, 0.25, 0.25));
+        // Directional lights have no distance to attenuate over.
+        let d = DirectionalLight::new(TypedVec::vector(0f64, -1f64, 0f64), *WHITE);
+        let samples = Light::from(d).samples(TypedVec::point(0f64, 0f64, 0f64));
+        assert_eq!(samples[0].intensity, *WHITE);
+    }
+}
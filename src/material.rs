@@ -1,6 +1,6 @@
 use crate::colour::{Colour, BLACK, WHITE};
 use crate::hittable::Hittable;
-use crate::lighting::Point;
+use crate::lighting::Light;
 use crate::pattern::Pattern;
 use crate::vec3::TypedVec;
 
@@ -34,31 +34,52 @@ impl Default for Material {
 }
 
 impl Material {
-    pub fn lighting<'l>(
+    /// `in_shadow` runs from 0 for fully lit to 1 for fully shadowed.
+    pub fn lighting(
         &self,
-        object: &'l dyn Hittable,
-        light: Point,
+        object: &dyn Hittable,
+        light: Light,
         point: TypedVec,
         eyev: TypedVec,
         normalv: TypedVec,
-        in_shadow: bool,
+        in_shadow: f64,
     ) -> Colour {
-        let colour = if let Some(pattern) = &self.pattern {
-            object.pattern_at(pattern, point).unwrap()
+        let surface = self.surface_at(object, point, 0f64);
+        self.shade(surface, light, point, eyev, normalv, in_shadow)
+    }
+
+    pub fn surface_at(&self, object: &dyn Hittable, point: TypedVec, time: f64) -> Colour {
+        if let Some(pattern) = &self.pattern {
+            object.pattern_at_time(pattern, point, time).unwrap()
         } else {
             self.colour
-        } * light.intensity;
+        }
+    }
 
-        let lightv = (light.position - point).normalize();
-        let ambient = colour * self.ambient;
+    pub(crate) fn shade(
+        &self,
+        surface: Colour,
+        light: Light,
+        point: TypedVec,
+        eyev: TypedVec,
+        normalv: TypedVec,
+        in_shadow: f64,
+    ) -> Colour {
+        let ambient = surface * light.intensity() * self.ambient;
 
-        if in_shadow {
+        if in_shadow >= 1f64 {
             return ambient;
         }
-        let light_dot_normal = lightv.dot_product(normalv);
-        let (diffuse, specular) = if light_dot_normal < 0f64 {
-            (*BLACK, *BLACK)
-        } else {
+        let samples = light.samples(point);
+        let mut diffuse_sum = *BLACK;
+        let mut specular_sum = *BLACK;
+        for sample in samples.iter() {
+            let colour = surface * sample.intensity;
+            let lightv = sample.direction;
+            let light_dot_normal = lightv.dot_product(normalv);
+            if light_dot_normal < 0f64 {
+                continue;
+            }
             let diffuse = colour * self.diffuse * light_dot_normal;
             let reflectv = -lightv.reflect(normalv);
             let reflect_dot_eye = reflectv.dot_product(eyev);
@@ -66,11 +87,13 @@ impl Material {
                 *BLACK
             } else {
                 let f = reflect_dot_eye.powf(self.shininess);
-                light.intensity * self.specular * f
+                sample.intensity * self.specular * f
             };
-            (diffuse, specular)
-        };
-        ambient + diffuse + specular
+            diffuse_sum = diffuse_sum + diffuse;
+            specular_sum = specular_sum + specular;
+        }
+        let scale = (1f64 - in_shadow) / samples.len() as f64;
+        ambient + diffuse_sum * scale + specular_sum * scale
     }
 }
 
@@ -84,6 +107,7 @@ mod test {
     use crate::sphere::Sphere;
     use crate::vec3::TypedVec;
     use lazy_static::lazy_static;
+    use std::f64::consts::PI;
 
     lazy_static! {
         static ref M: Material = Material::default();
@@ -96,7 +120,7 @@ mod test {
         let eyev = TypedVec::vector(0f64, 0f64, -1f64);
         let normalv = TypedVec::vector(0f64, 0f64, -1f64);
         let l = lighting::Point::new(TypedVec::point(0f64, 0f64, -10f64), *WHITE);
-        let r = M.lighting(&s, l, *POSITION, eyev, normalv, false);
+        let r = M.lighting(&s, l.into(), *POSITION, eyev, normalv, 0f64);
         assert_eq!(r, Colour::new(1.9, 1.9, 1.9))
     }
 
@@ -106,7 +130,7 @@ mod test {
         let eyev = TypedVec::vector(0f64, 2f64.sqrt() / 2f64, -2f64.sqrt() / 2f64);
         let normalv = TypedVec::vector(0f64, 0f64, -1f64);
         let l = lighting::Point::new(TypedVec::point(0f64, 0f64, -10f64), *WHITE);
-        let r = M.lighting(&s, l, *POSITION, eyev, normalv, false);
+        let r = M.lighting(&s, l.into(), *POSITION, eyev, normalv, 0f64);
         assert_eq!(r, Colour::new(1.0, 1.0, 1.0))
     }
 
@@ -116,7 +140,7 @@ mod test {
         let eyev = TypedVec::vector(0f64, 0f64, -1f64);
         let normalv = TypedVec::vector(0f64, 0f64, -1f64);
         let l = lighting::Point::new(TypedVec::point(0f64, 10f64, -10f64), *WHITE);
-        let r = M.lighting(&s, l, *POSITION, eyev, normalv, false);
+        let r = M.lighting(&s, l.into(), *POSITION, eyev, normalv, 0f64);
         assert_eq!(r.round(10000f64), Colour::new(0.7364, 0.7364, 0.7364))
     }
 
@@ -126,7 +150,7 @@ mod test {
         let eyev = TypedVec::vector(0f64, -2f64.sqrt() / 2f64, -2f64.sqrt() / 2f64);
         let normalv = TypedVec::vector(0f64, 0f64, -1f64);
         let l = lighting::Point::new(TypedVec::point(0f64, 10f64, -10f64), *WHITE);
-        let r = M.lighting(&s, l, *POSITION, eyev, normalv, false);
+        let r = M.lighting(&s, l.into(), *POSITION, eyev, normalv, 0f64);
         assert_eq!(r.round(10000f64), Colour::new(1.6364, 1.6364, 1.6364))
     }
 
@@ -136,7 +160,7 @@ mod test {
         let eyev = TypedVec::vector(0f64, 0f64, -1f64);
         let normalv = TypedVec::vector(0f64, 0f64, -1f64);
         let l = lighting::Point::new(TypedVec::point(0f64, 0f64, 10f64), *WHITE);
-        let r = M.lighting(&s, l, *POSITION, eyev, normalv, false);
+        let r = M.lighting(&s, l.into(), *POSITION, eyev, normalv, 0f64);
         assert_eq!(r.round(100f64), Colour::new(0.1, 0.1, 0.1))
     }
 
@@ -146,7 +170,7 @@ mod test {
         let eyev = TypedVec::vector(0f64, 0f64, -1f64);
         let normalv = TypedVec::vector(0f64, 0f64, -1f64);
         let l = lighting::Point::new(TypedVec::point(0f64, 0f64, -10f64), *WHITE);
-        let r = M.lighting(&s, l, *POSITION, eyev, normalv, true);
+        let r = M.lighting(&s, l.into(), *POSITION, eyev, normalv, 1f64);
         assert_eq!(r.round(100f64), Colour::new(0.1, 0.1, 0.1))
     }
 
@@ -164,9 +188,99 @@ mod test {
         let eyev = TypedVec::vector(0f64, 0f64, -1f64);
         let normalv = TypedVec::vector(0f64, 0f64, -1f64);
         let l = lighting::Point::new(TypedVec::point(0f64, 0f64, -10f64), *WHITE);
-        let c1 = m.lighting(&s, l, TypedVec::point(0.9, 0f64, 0f64), eyev, normalv, true);
-        let c2 = m.lighting(&s, l, TypedVec::point(1.1, 0f64, 0f64), eyev, normalv, true);
+        let c1 = m.lighting(
+            &s,
+            l.into(),
+            TypedVec::point(0.9, 0f64, 0f64),
+            eyev,
+            normalv,
+            1f64,
+        );
+        let c2 = m.lighting(
+            &s,
+            l.into(),
+            TypedVec::point(1.1, 0f64, 0f64),
+            eyev,
+            normalv,
+            1f64,
+        );
         assert_eq!(c1, *WHITE);
         assert_eq!(c2, *BLACK);
     }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn test_area_light_lighting() {
+        let l = lighting::AreaLight::new(
+            TypedVec::point(-0.5, -0.5, -5f64),
+            TypedVec::vector(1f64, 0f64, 0f64),
+            2,
+            TypedVec::vector(0f64, 1f64, 0f64),
+            2,
+            *WHITE,
+        );
+        let mut s = Sphere::default();
+        s.material.specular = 0f64;
+        let eye = TypedVec::point(0f64, 0f64, -5f64);
+        let examples = vec![
+            (TypedVec::point(0f64, 0f64, -1f64), 0.9965),
+            (TypedVec::point(0f64, 0.7071, -0.7071), 0.6232),
+        ];
+        for (p, expected) in examples {
+            dbg!(&p);
+            let eyev = (eye - p).normalize();
+            let normalv = TypedVec::vector(p.x, p.y, p.z);
+            let r = s.material.lighting(&s, l.into(), p, eyev, normalv, 0f64);
+            assert_eq!(r.round(10000f64), Colour::new(expected, expected, expected));
+        }
+    }
+
+    #[test]
+    fn test_spot_light_lighting() {
+        let s = Sphere::default();
+        let eyev = TypedVec::vector(0f64, 0f64, -1f64);
+        let normalv = TypedVec::vector(0f64, 0f64, -1f64);
+        let examples = vec![
+            ("facing", TypedVec::vector(0f64, 0f64, 1f64), 1.9),
+            ("facing away", TypedVec::vector(0f64, 0f64, -1f64), 0.1),
+        ];
+        for (name, direction, expected) in examples {
+            dbg!(&name);
+            let l = lighting::SpotLight::new(
+                TypedVec::point(0f64, 0f64, -10f64),
+                direction,
+                PI / 8f64,
+                PI / 4f64,
+                *WHITE,
+            );
+            let r = M.lighting(&s, l.into(), *POSITION, eyev, normalv, 0f64);
+            assert_eq!(r.round(10000f64), Colour::new(expected, expected, expected));
+        }
+    }
+
+    #[test]
+    fn test_directional_light_lighting() {
+        let s = Sphere::default();
+        let eyev = TypedVec::vector(0f64, 0f64, -1f64);
+        let normalv = TypedVec::vector(0f64, 0f64, -1f64);
+        let l = lighting::DirectionalLight::new(TypedVec::vector(0f64, -1f64, 1f64), *WHITE);
+        let point = lighting::Point::new(TypedVec::point(0f64, 10f64, -10f64), *WHITE);
+        for p in [*POSITION, TypedVec::point(3f64, 0f64, 0f64)] {
+            let r = M.lighting(&s, l.into(), p, eyev, normalv, 0f64);
+            let expected = M.lighting(&s, point.into(), *POSITION, eyev, normalv, 0f64);
+            assert_eq!(r.round(10000f64), expected.round(10000f64));
+        }
+    }
+
+    #[test]
+    fn test_attenuated_lighting() {
+        let s = Sphere::default();
+        let eyev = TypedVec::vector(0f64, 0f64, -1f64);
+        let normalv = TypedVec::vector(0f64, 0f64, -1f64);
+        let mut l = lighting::Point::new(TypedVec::point(0f64, 0f64, -10f64), *WHITE);
+        l.set_attenuation(lighting::Attenuation::InverseSquare);
+        let r = M.lighting(&s, l.into(), *POSITION, eyev, normalv, 0f64);
+        // Ambient is left alone; diffuse and specular drop to 1/100th.
+        assert_eq!(r.round(10000f64), Colour::new(0.118, 0.118, 0.118));
+    }
 }
@@ -153,6 +153,8 @@ where
         }
     }
 
+    // Transforms use `Matrix4` now, so only the tests reach these.
+    #[cfg_attr(not(test), allow(dead_code))]
     pub(crate) fn transpose(&self) -> Matrix<T> {
         Self {
             rows: self.rows,
@@ -169,7 +171,8 @@ where
         }
     }
 
-    fn determinant(&self) -> T {
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn determinant(&self) -> T {
         if self.rows == 2 && self.cols == 2 {
             return self.data[0] * self.data[3] - self.data[1] * self.data[2];
         }
@@ -204,25 +207,29 @@ where
         }
     }
 
+    #[cfg_attr(not(test), allow(dead_code))]
     fn minor(&self, row: usize, col: usize) -> T {
         let m = self.submatrix(row, col);
         m.determinant()
     }
 
+    #[cfg_attr(not(test), allow(dead_code))]
     fn cofactor(&self, row: usize, col: usize) -> T {
         let point = row + col;
         let m = self.minor(row, col);
-        if point % 2 == 0 {
+        if point.is_multiple_of(2) {
             m
         } else {
             -m
         }
     }
 
+    #[cfg_attr(not(test), allow(dead_code))]
     fn invertible(&self) -> bool {
         !self.determinant().is_zero()
     }
 
+    #[cfg_attr(not(test), allow(dead_code))]
     pub(crate) fn inverse(&self) -> Result<Matrix<T>> {
         if !self.invertible() {
             return Err(anyhow!("matrix isn't invertible"));
@@ -401,9 +408,9 @@ where
         assert!(self.cols == 4);
         let vec = vec![rhs.x, rhs.y, rhs.z, rhs.w];
         TypedVec {
-            x: { mul_int(&self, &vec, 0) },
-            y: { mul_int(&self, &vec, 1) },
-            z: { mul_int(&self, &vec, 2) },
+            x: { mul_int(self, &vec, 0) },
+            y: { mul_int(self, &vec, 1) },
+            z: { mul_int(self, &vec, 2) },
             w: rhs.w,
             is: rhs.is,
         }
@@ -0,0 +1,330 @@
+use crate::matrix::{Axis, Matrix};
+use crate::vec3::TypedVec;
+use anyhow::*;
+use std::convert::TryFrom;
+use std::ops::Mul;
+
+/// A `Copy` 4x4 matrix with an unrolled product and closed-form inverse.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Matrix4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(rows: [[f64; 4]; 4]) -> Self {
+        Self { m: rows }
+    }
+
+    pub fn identity() -> Self {
+        Self::new([
+            [1f64, 0f64, 0f64, 0f64],
+            [0f64, 1f64, 0f64, 0f64],
+            [0f64, 0f64, 1f64, 0f64],
+            [0f64, 0f64, 0f64, 1f64],
+        ])
+    }
+
+    pub fn translation(x: f64, y: f64, z: f64) -> Self {
+        Self::new([
+            [1f64, 0f64, 0f64, x],
+            [0f64, 1f64, 0f64, y],
+            [0f64, 0f64, 1f64, z],
+            [0f64, 0f64, 0f64, 1f64],
+        ])
+    }
+
+    pub fn scaling(x: f64, y: f64, z: f64) -> Self {
+        Self::new([
+            [x, 0f64, 0f64, 0f64],
+            [0f64, y, 0f64, 0f64],
+            [0f64, 0f64, z, 0f64],
+            [0f64, 0f64, 0f64, 1f64],
+        ])
+    }
+
+    pub fn rotation(axis: Axis, distance: f64) -> Self {
+        let (s, c) = distance.sin_cos();
+        match axis {
+            Axis::X => Self::new([
+                [1f64, 0f64, 0f64, 0f64],
+                [0f64, c, -s, 0f64],
+                [0f64, s, c, 0f64],
+                [0f64, 0f64, 0f64, 1f64],
+            ]),
+            Axis::Y => Self::new([
+                [c, 0f64, s, 0f64],
+                [0f64, 1f64, 0f64, 0f64],
+                [-s, 0f64, c, 0f64],
+                [0f64, 0f64, 0f64, 1f64],
+            ]),
+            Axis::Z => Self::new([
+                [c, -s, 0f64, 0f64],
+                [s, c, 0f64, 0f64],
+                [0f64, 0f64, 1f64, 0f64],
+                [0f64, 0f64, 0f64, 1f64],
+            ]),
+        }
+    }
+
+    pub fn shearing(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Self {
+        Self::new([
+            [1f64, xy, xz, 0f64],
+            [yx, 1f64, yz, 0f64],
+            [zx, zy, 1f64, 0f64],
+            [0f64, 0f64, 0f64, 1f64],
+        ])
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<f64> {
+        self.m.get(row).and_then(|r| r.get(col)).copied()
+    }
+
+    pub fn lerp(&self, other: &Self, t: f64) -> Self {
+        let mut out = *self;
+        for (row, other) in out.m.iter_mut().zip(other.m.iter()) {
+            for (a, b) in row.iter_mut().zip(other.iter()) {
+                *a += (*b - *a) * t;
+            }
+        }
+        out
+    }
+
+    pub fn transpose(&self) -> Self {
+        let m = &self.m;
+        Self::new([
+            [m[0][0], m[1][0], m[2][0], m[3][0]],
+            [m[0][1], m[1][1], m[2][1], m[3][1]],
+            [m[0][2], m[1][2], m[2][2], m[3][2]],
+            [m[0][3], m[1][3], m[2][3], m[3][3]],
+        ])
+    }
+
+    // The 2x2 determinants from the top two rows (`s`) and the bottom two
+    // (`c`), which both the determinant and the inverse are built from.
+    fn pairs(&self) -> ([f64; 6], [f64; 6]) {
+        let m = &self.m;
+        let s = [
+            m[0][0] * m[1][1] - m[1][0] * m[0][1],
+            m[0][0] * m[1][2] - m[1][0] * m[0][2],
+            m[0][0] * m[1][3] - m[1][0] * m[0][3],
+            m[0][1] * m[1][2] - m[1][1] * m[0][2],
+            m[0][1] * m[1][3] - m[1][1] * m[0][3],
+            m[0][2] * m[1][3] - m[1][2] * m[0][3],
+        ];
+        let c = [
+            m[2][0] * m[3][1] - m[3][0] * m[2][1],
+            m[2][0] * m[3][2] - m[3][0] * m[2][2],
+            m[2][0] * m[3][3] - m[3][0] * m[2][3],
+            m[2][1] * m[3][2] - m[3][1] * m[2][2],
+            m[2][1] * m[3][3] - m[3][1] * m[2][3],
+            m[2][2] * m[3][3] - m[3][2] * m[2][3],
+        ];
+        (s, c)
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let (s, c) = self.pairs();
+        determinant(&s, &c)
+    }
+
+    pub fn inverse(&self) -> Result<Self> {
+        let (s, c) = self.pairs();
+        let det = determinant(&s, &c);
+        if det == 0f64 {
+            return Err(anyhow!("matrix isn't invertible"));
+        }
+        let inv = 1f64 / det;
+        let m = &self.m;
+        Ok(Self::new([
+            [
+                (m[1][1] * c[5] - m[1][2] * c[4] + m[1][3] * c[3]) * inv,
+                (-m[0][1] * c[5] + m[0][2] * c[4] - m[0][3] * c[3]) * inv,
+                (m[3][1] * s[5] - m[3][2] * s[4] + m[3][3] * s[3]) * inv,
+                (-m[2][1] * s[5] + m[2][2] * s[4] - m[2][3] * s[3]) * inv,
+            ],
+            [
+                (-m[1][0] * c[5] + m[1][2] * c[2] - m[1][3] * c[1]) * inv,
+                (m[0][0] * c[5] - m[0][2] * c[2] + m[0][3] * c[1]) * inv,
+                (-m[3][0] * s[5] + m[3][2] * s[2] - m[3][3] * s[1]) * inv,
+                (m[2][0] * s[5] - m[2][2] * s[2] + m[2][3] * s[1]) * inv,
+            ],
+            [
+                (m[1][0] * c[4] - m[1][1] * c[2] + m[1][3] * c[0]) * inv,
+                (-m[0][0] * c[4] + m[0][1] * c[2] - m[0][3] * c[0]) * inv,
+                (m[3][0] * s[4] - m[3][1] * s[2] + m[3][3] * s[0]) * inv,
+                (-m[2][0] * s[4] + m[2][1] * s[2] - m[2][3] * s[0]) * inv,
+            ],
+            [
+                (-m[1][0] * c[3] + m[1][1] * c[1] - m[1][2] * c[0]) * inv,
+                (m[0][0] * c[3] - m[0][1] * c[1] + m[0][2] * c[0]) * inv,
+                (-m[3][0] * s[3] + m[3][1] * s[1] - m[3][2] * s[0]) * inv,
+                (m[2][0] * s[3] - m[2][1] * s[1] + m[2][2] * s[0]) * inv,
+            ],
+        ]))
+    }
+
+    #[cfg(test)]
+    pub(crate) fn round(&self, factor: f64) -> Self {
+        let mut out = *self;
+        for v in out.m.iter_mut().flatten() {
+            *v = (*v * factor).round() / factor;
+        }
+        out
+    }
+}
+
+fn determinant(s: &[f64; 6], c: &[f64; 6]) -> f64 {
+    s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+}
+
+impl TryFrom<&Matrix<f64>> for Matrix4 {
+    type Error = Error;
+
+    fn try_from(m: &Matrix<f64>) -> Result<Self> {
+        let mut out = Self::new([[0f64; 4]; 4]);
+        for (r, row) in out.m.iter_mut().enumerate() {
+            for (c, v) in row.iter_mut().enumerate() {
+                *v = *m.get(r, c).ok_or_else(|| anyhow!("matrix isn't 4x4"))?;
+            }
+        }
+        if m.get(4, 0).is_some() || m.get(0, 4).is_some() {
+            return Err(anyhow!("matrix isn't 4x4"));
+        }
+        Ok(out)
+    }
+}
+
+impl Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Matrix4) -> Self::Output {
+        let (a, b) = (&self.m, &rhs.m);
+        let row = |r: usize| {
+            [
+                a[r][0] * b[0][0] + a[r][1] * b[1][0] + a[r][2] * b[2][0] + a[r][3] * b[3][0],
+                a[r][0] * b[0][1] + a[r][1] * b[1][1] + a[r][2] * b[2][1] + a[r][3] * b[3][1],
+                a[r][0] * b[0][2] + a[r][1] * b[1][2] + a[r][2] * b[2][2] + a[r][3] * b[3][2],
+                a[r][0] * b[0][3] + a[r][1] * b[1][3] + a[r][2] * b[2][3] + a[r][3] * b[3][3],
+            ]
+        };
+        Matrix4::new([row(0), row(1), row(2), row(3)])
+    }
+}
+
+impl Mul<TypedVec> for Matrix4 {
+    type Output = TypedVec;
+
+    fn mul(self, rhs: TypedVec) -> Self::Output {
+        &self * rhs
+    }
+}
+
+impl Mul<TypedVec> for &'_ Matrix4 {
+    type Output = TypedVec;
+
+    fn mul(self, rhs: TypedVec) -> Self::Output {
+        let m = &self.m;
+        let row = |r: usize| m[r][0] * rhs.x + m[r][1] * rhs.y + m[r][2] * rhs.z + m[r][3] * rhs.w;
+        TypedVec {
+            x: row(0),
+            y: row(1),
+            z: row(2),
+            w: rhs.w,
+            is: rhs.is,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matrix::{Axis, Matrix};
+    use crate::matrix4::Matrix4;
+    use crate::vec3::TypedVec;
+    use std::convert::TryFrom;
+
+    fn generic(m: &Matrix4) -> Matrix<f64> {
+        Matrix::from_iter(4, 4, (0..16).map(|i| m.get(i / 4, i % 4).unwrap()))
+    }
+
+    #[test]
+    fn test_matches_matrix() {
+        let a = Matrix4::new([
+            [-5f64, 2f64, 6f64, -8f64],
+            [1f64, -5f64, 1f64, 8f64],
+            [7f64, 7f64, -6f64, -7f64],
+            [1f64, -3f64, 7f64, 4f64],
+        ]);
+        let examples = vec![
+            ("book", a),
+            ("translation", Matrix4::translation(5f64, -3f64, 2f64)),
+            ("scaling", Matrix4::scaling(2f64, 3f64, 4f64)),
+            ("rotation", Matrix4::rotation(Axis::Y, 0.7)),
+            (
+                "shearing",
+                Matrix4::shearing(1f64, 2f64, 3f64, 4f64, 5f64, 6f64),
+            ),
+            (
+                "chain",
+                Matrix4::translation(10f64, 5f64, 7f64)
+                    * Matrix4::scaling(5f64, 5f64, 5f64)
+                    * Matrix4::rotation(Axis::X, 1.2),
+            ),
+        ];
+        let b = Matrix4::new([
+            [-2f64, 1f64, 2f64, 3f64],
+            [3f64, 2f64, 1f64, -1f64],
+            [4f64, 3f64, 6f64, 5f64],
+            [1f64, 2f64, 7f64, 8f64],
+        ]);
+        let p = TypedVec::point(1f64, -2f64, 3f64);
+        for (name, m) in examples {
+            dbg!(&name);
+            let g = generic(&m);
+            assert_eq!(generic(&m.transpose()), g.transpose());
+            assert_eq!(
+                crate::roundf(m.determinant(), 100000f64),
+                crate::roundf(g.determinant(), 100000f64)
+            );
+            assert_eq!(
+                generic(&m.inverse().unwrap().round(100000f64)),
+                g.inverse().unwrap().round(100000f64)
+            );
+            assert_eq!(generic(&(m * b)), g.clone() * generic(&b));
+            assert_eq!(m * p, g * p);
+            assert_eq!(Matrix4::try_from(&generic(&m)).unwrap(), m);
+        }
+    }
+
+    #[test]
+    fn test_identity() {
+        let a = Matrix4::translation(1f64, 2f64, 3f64) * Matrix4::rotation(Axis::Z, 0.3);
+        assert_eq!(a * Matrix4::identity(), a);
+        assert_eq!(Matrix4::identity() * a, a);
+        assert_eq!(
+            (a * a.inverse().unwrap()).round(100000f64),
+            Matrix4::identity()
+        );
+    }
+
+    #[test]
+    fn test_not_invertible() {
+        let m = Matrix4::new([
+            [-4f64, 2f64, -2f64, -3f64],
+            [9f64, 6f64, 2f64, 6f64],
+            [0f64, -5f64, 1f64, -5f64],
+            [0f64, 0f64, 0f64, 0f64],
+        ]);
+        assert_eq!(m.determinant(), 0f64);
+        assert!(m.inverse().is_err());
+        assert!(Matrix4::try_from(&Matrix::<f64>::identity(3)).is_err());
+    }
+
+    #[test]
+    fn test_vector() {
+        let v = TypedVec::vector(-4f64, 6f64, 8f64);
+        let m = Matrix4::translation(5f64, -3f64, 2f64);
+        assert_eq!(m * v, v);
+        let m = Matrix4::scaling(2f64, 3f64, 4f64);
+        assert_eq!(m * v, TypedVec::vector(-8f64, 18f64, 32f64));
+    }
+}
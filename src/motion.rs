@@ -0,0 +1,124 @@
+use crate::bounds::BoundingBox;
+use crate::matrix4::Matrix4;
+use std::cmp::Ordering;
+
+/// Used in place of `transform` while the shutter is open.
+// Both matrices sit inline; boxing them would cost a pointer chase per ray
+// for the handful of objects that move.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+pub enum Motion {
+    /// Exact for moving and scaling; rotations shrink part way, so use `Function`.
+    Linear {
+        start: Matrix4,
+        end: Matrix4,
+    },
+    Function(fn(f64) -> Matrix4),
+}
+
+impl Motion {
+    pub fn linear(start: Matrix4, end: Matrix4) -> Self {
+        Motion::Linear { start, end }
+    }
+
+    pub fn at(&self, time: f64) -> Matrix4 {
+        match self {
+            Motion::Linear { start, end } => start.lerp(end, time.clamp(0f64, 1f64)),
+            Motion::Function(f) => f(time),
+        }
+    }
+
+    /// Everywhere `local` sweeps through; unbounded for `Function`.
+    pub fn bounds(&self, local: &BoundingBox) -> BoundingBox {
+        match self {
+            Motion::Linear { start, end } => {
+                let mut bounds = local.transform(start);
+                bounds.merge(&local.transform(end));
+                bounds
+            }
+            Motion::Function(_) => BoundingBox::infinite(),
+        }
+    }
+}
+
+// The same function can end up at different addresses, so functions only
+// compare equal when their pointers happen to match, and never order.
+impl PartialEq for Motion {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Motion::Linear { start: a, end: b }, Motion::Linear { start: c, end: d }) => {
+                a == c && b == d
+            }
+            (Motion::Function(f), Motion::Function(g)) => std::ptr::fn_addr_eq(*f, *g),
+            _ => false,
+        }
+    }
+}
+
+impl PartialOrd for Motion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Motion::Linear { start: a, end: b }, Motion::Linear { start: c, end: d }) => {
+                (a, b).partial_cmp(&(c, d))
+            }
+            _ if self == other => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bounds::BoundingBox;
+    use crate::matrix::Axis;
+    use crate::matrix4::Matrix4;
+    use crate::motion::Motion;
+    use crate::vec3::TypedVec;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_linear_motion() {
+        let m = Motion::linear(
+            Matrix4::translation(0f64, 0f64, 0f64),
+            Matrix4::translation(4f64, 0f64, -2f64),
+        );
+        let examples = vec![
+            ("start", 0f64, (0f64, 0f64, 0f64)),
+            ("middle", 0.5, (2f64, 0f64, -1f64)),
+            ("end", 1f64, (4f64, 0f64, -2f64)),
+            ("before", -1f64, (0f64, 0f64, 0f64)),
+            ("after", 3f64, (4f64, 0f64, -2f64)),
+        ];
+        for (name, time, (x, y, z)) in examples {
+            dbg!(&name);
+            assert_eq!(m.at(time), Matrix4::translation(x, y, z));
+        }
+    }
+
+    #[test]
+    fn test_function_motion() {
+        let m = Motion::Function(|t| Matrix4::rotation(Axis::Y, t * PI));
+        assert_eq!(m.at(0.25), Matrix4::rotation(Axis::Y, PI / 4f64));
+        assert_eq!(
+            m.bounds(&BoundingBox::new(
+                TypedVec::point(-1f64, -1f64, -1f64),
+                TypedVec::point(1f64, 1f64, 1f64),
+            )),
+            BoundingBox::infinite()
+        );
+    }
+
+    #[test]
+    fn test_linear_bounds() {
+        let m = Motion::linear(
+            Matrix4::translation(0f64, 0f64, 0f64),
+            Matrix4::translation(4f64, 0f64, 0f64) * Matrix4::scaling(2f64, 2f64, 2f64),
+        );
+        let b = m.bounds(&BoundingBox::new(
+            TypedVec::point(-1f64, -1f64, -1f64),
+            TypedVec::point(1f64, 1f64, 1f64),
+        ));
+        assert_eq!(b.min, TypedVec::point(-1f64, -2f64, -2f64));
+        assert_eq!(b.max, TypedVec::point(6f64, 2f64, 2f64));
+    }
+}
@@ -0,0 +1,482 @@
+use crate::group::{Group, Groupable};
+use crate::smooth_triangle::SmoothTriangle;
+use crate::triangle::Triangle;
+use crate::vec3::TypedVec;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjError {
+    Ignored {
+        line: usize,
+        content: String,
+    },
+    Malformed {
+        line: usize,
+        content: String,
+        reason: String,
+    },
+}
+
+impl Display for ObjError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjError::Ignored { line, content } => {
+                write!(f, "line {}: ignored `{}`", line, content)
+            }
+            ObjError::Malformed {
+                line,
+                content,
+                reason,
+            } => write!(f, "line {}: {} in `{}`", line, reason, content),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+#[derive(Debug, Default)]
+struct ObjGroup<'a> {
+    name: Option<String>,
+    triangles: Vec<Triangle<'a>>,
+    smooth: Vec<SmoothTriangle<'a>>,
+    // `None` for the default group, whose faces go straight into `top`
+    group: Option<Arc<Group<'a>>>,
+}
+
+impl<'a> ObjGroup<'a> {
+    fn len(&self) -> usize {
+        self.triangles.len() + self.smooth.len()
+    }
+}
+
+/// `to_group` lends the file's triangles out as a `Group`.
+#[derive(Debug, Default)]
+pub struct ObjFile<'a> {
+    pub vertices: Vec<TypedVec>,
+    pub normals: Vec<TypedVec>,
+    pub texture_coords: Vec<(f64, f64)>,
+    pub ignored: Vec<ObjError>,
+    groups: Vec<ObjGroup<'a>>,
+    top: Arc<Group<'a>>,
+    // index into `groups` that faces are added to
+    current: usize,
+}
+
+impl<'a> ObjFile<'a> {
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let input = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&input)?)
+    }
+
+    pub fn parse(input: &str) -> Result<Self, ObjError> {
+        let mut obj = ObjFile {
+            groups: vec![ObjGroup::default()],
+            ..Default::default()
+        };
+        for (n, content) in input.lines().enumerate() {
+            obj.parse_line(n + 1, content)?;
+        }
+        Ok(obj)
+    }
+
+    /// The default group first, then named groups in the order they appeared.
+    pub fn triangle_counts(&self) -> Vec<(Option<&str>, usize)> {
+        self.groups
+            .iter()
+            .map(|g| (g.name.as_deref(), g.len()))
+            .collect()
+    }
+
+    pub fn triangles(&self, name: Option<&str>) -> Option<&[Triangle<'a>]> {
+        self.groups
+            .iter()
+            .find(|g| g.name.as_deref() == name)
+            .map(|g| g.triangles.as_slice())
+    }
+
+    pub fn smooth_triangles(&self, name: Option<&str>) -> Option<&[SmoothTriangle<'a>]> {
+        self.groups
+            .iter()
+            .find(|g| g.name.as_deref() == name)
+            .map(|g| g.smooth.as_slice())
+    }
+
+    /// Named groups become child groups, each with its own BVH.
+    pub fn to_group(&'a self) -> Arc<Group<'a>> {
+        // parent links were set while parsing, and the children only need
+        // lending out the first time
+        if self.top.is_empty() {
+            for g in self.groups.iter() {
+                let target: &Group<'a> = match &g.group {
+                    Some(inner) => {
+                        self.top.set_child(&**inner);
+                        inner
+                    }
+                    None => &self.top,
+                };
+                g.triangles.iter().for_each(|t| target.set_child(t));
+                g.smooth.iter().for_each(|t| target.set_child(t));
+                if g.group.is_some() {
+                    target.build_bvh();
+                }
+            }
+            self.top.build_bvh();
+        }
+        Arc::clone(&self.top)
+    }
+
+    fn parse_line(&mut self, line: usize, content: &str) -> Result<(), ObjError> {
+        let malformed = |reason: &str| ObjError::Malformed {
+            line,
+            content: content.to_string(),
+            reason: reason.to_string(),
+        };
+        let mut words = content.split_whitespace();
+        match words.next() {
+            // an optional fourth weight only matters for rational curves
+            Some("v") => {
+                let v = parse_floats(words, 3, 4).map_err(|e| malformed(&e))?;
+                self.vertices.push(TypedVec::point(v[0], v[1], v[2]));
+            }
+            Some("vn") => {
+                let v = parse_floats(words, 3, 3).map_err(|e| malformed(&e))?;
+                self.normals.push(TypedVec::vector(v[0], v[1], v[2]));
+            }
+            Some("vt") => {
+                let uv = parse_floats(words, 1, 3).map_err(|e| malformed(&e))?;
+                self.texture_coords
+                    .push((uv[0], uv.get(1).copied().unwrap_or(0f64)));
+            }
+            Some("f") => {
+                let refs = words
+                    .map(|w| self.face_vertex(w))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| malformed(&e))?;
+                if refs.len() < 3 {
+                    return Err(malformed("a face needs at least three vertices"));
+                }
+                self.add_face(&refs);
+            }
+            Some("g") | Some("o") => {
+                // a bare `g` goes back to the default group, and a name
+                // seen before carries on with that group
+                let name = words.collect::<Vec<_>>().join(" ");
+                let name = if name.is_empty() { None } else { Some(name) };
+                self.current = match self.groups.iter().position(|g| g.name == name) {
+                    Some(i) => i,
+                    None => {
+                        let mut inner = Group::new();
+                        inner.set_parent(&self.top);
+                        self.groups.push(ObjGroup {
+                            name,
+                            group: Some(Arc::new(inner)),
+                            ..Default::default()
+                        });
+                        self.groups.len() - 1
+                    }
+                };
+            }
+            None => (),
+            Some(_) => self.ignored.push(ObjError::Ignored {
+                line,
+                content: content.to_string(),
+            }),
+        }
+        Ok(())
+    }
+
+    fn face_vertex(&self, word: &str) -> Result<(TypedVec, Option<TypedVec>), String> {
+        let mut parts = word.split('/');
+        let v = resolve(parts.next(), &self.vertices, "vertex")?
+            .ok_or_else(|| format!("missing vertex index `{}`", word))?;
+        resolve(parts.next(), &self.texture_coords, "texture coordinate")?;
+        let n = resolve(parts.next(), &self.normals, "normal")?;
+        if parts.next().is_some() {
+            return Err(format!("too many indices in `{}`", word));
+        }
+        Ok((v, n))
+    }
+
+    // Polygons are split into a fan of triangles around the first vertex.
+    fn add_face(&mut self, refs: &[(TypedVec, Option<TypedVec>)]) {
+        let group = &mut self.groups[self.current];
+        let parent = Arc::clone(group.group.as_ref().unwrap_or(&self.top));
+        let (p1, n1) = refs[0];
+        for pair in refs[1..].windows(2) {
+            let (p2, n2) = pair[0];
+            let (p3, n3) = pair[1];
+            match (n1, n2, n3) {
+                (Some(n1), Some(n2), Some(n3)) => {
+                    let mut t = SmoothTriangle::new(p1, p2, p3, n1, n2, n3);
+                    t.set_parent(&parent);
+                    group.smooth.push(t);
+                }
+                _ => {
+                    let mut t = Triangle::new(p1, p2, p3);
+                    t.set_parent(&parent);
+                    group.triangles.push(t);
+                }
+            }
+        }
+    }
+}
+
+fn parse_floats<'w>(
+    words: impl Iterator<Item = &'w str>,
+    min: usize,
+    max: usize,
+) -> Result<Vec<f64>, String> {
+    let values = words
+        .map(|w| {
+            w.parse::<f64>()
+                .map_err(|_| format!("`{}` isn't a number", w))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if values.len() < min || values.len() > max {
+        return Err(format!(
+            "expected {} to {} numbers, found {}",
+            min,
+            max,
+            values.len()
+        ));
+    }
+    Ok(values)
+}
+
+// OBJ indices start at one; negative indices count back from the most
+// recent record.
+fn resolve<T: Copy>(index: Option<&str>, items: &[T], kind: &str) -> Result<Option<T>, String> {
+    let index = match index {
+        None | Some("") => return Ok(None),
+        Some(i) => i,
+    };
+    let i = index
+        .parse::<isize>()
+        .map_err(|_| format!("`{}` isn't a {} index", index, kind))?;
+    let position = if i < 0 {
+        items.len() as isize + i
+    } else {
+        i - 1
+    };
+    if position < 0 || position as usize >= items.len() {
+        return Err(format!("{} {} doesn't exist", kind, i));
+    }
+    Ok(Some(items[position as usize]))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hittable::Hittable;
+    use crate::obj::{ObjError, ObjFile};
+    use crate::ray::Ray;
+    use crate::vec3::TypedVec;
+
+    #[test]
+    fn test_ignores_unrecognised_lines() {
+        let input = "There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.";
+        let obj = ObjFile::parse(input).unwrap();
+        assert_eq!(obj.ignored.len(), 5);
+        assert_eq!(
+            obj.ignored[1],
+            ObjError::Ignored {
+                line: 2,
+                content: "who traveled much faster than light.".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_vertex_records() {
+        let input = "v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0 1.0";
+        let obj = ObjFile::parse(input).unwrap();
+        assert_eq!(obj.vertices.len(), 4);
+        assert_eq!(obj.vertices[0], TypedVec::point(-1f64, 1f64, 0f64));
+        assert_eq!(obj.vertices[1], TypedVec::point(-1f64, 0.5, 0f64));
+        assert_eq!(obj.vertices[2], TypedVec::point(1f64, 0f64, 0f64));
+        assert_eq!(obj.vertices[3], TypedVec::point(1f64, 1f64, 0f64));
+    }
+
+    #[test]
+    fn test_triangle_faces() {
+        let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+f 1 3 4";
+        let obj = ObjFile::parse(input).unwrap();
+        let t = obj.triangles(None).unwrap();
+        assert_eq!(t[0].p1, obj.vertices[0]);
+        assert_eq!(t[0].p2, obj.vertices[1]);
+        assert_eq!(t[0].p3, obj.vertices[2]);
+        assert_eq!(t[1].p1, obj.vertices[0]);
+        assert_eq!(t[1].p2, obj.vertices[2]);
+        assert_eq!(t[1].p3, obj.vertices[3]);
+    }
+
+    #[test]
+    fn test_polygon_fan() {
+        let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+f 1 2 3 4 5";
+        let obj = ObjFile::parse(input).unwrap();
+        let t = obj.triangles(None).unwrap();
+        assert_eq!(t.len(), 3);
+        assert_eq!(t[2].p1, obj.vertices[0]);
+        assert_eq!(t[2].p2, obj.vertices[3]);
+        assert_eq!(t[2].p3, obj.vertices[4]);
+    }
+
+    #[test]
+    fn test_named_groups() {
+        let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+o SecondGroup
+f 1 3 4";
+        let obj = ObjFile::parse(input).unwrap();
+        assert_eq!(
+            obj.triangle_counts(),
+            vec![(None, 0), (Some("FirstGroup"), 1), (Some("SecondGroup"), 1)]
+        );
+        let t2 = obj.triangles(Some("SecondGroup")).unwrap();
+        assert_eq!(t2[0].p2, obj.vertices[2]);
+    }
+
+    #[test]
+    fn test_repeated_groups() {
+        let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4
+g
+f 1 2 4
+g FirstGroup
+f 2 3 4";
+        let obj = ObjFile::parse(input).unwrap();
+        assert_eq!(
+            obj.triangle_counts(),
+            vec![(None, 1), (Some("FirstGroup"), 2), (Some("SecondGroup"), 1)]
+        );
+        let t1 = obj.triangles(Some("FirstGroup")).unwrap();
+        assert_eq!(t1[1].p1, obj.vertices[1]);
+    }
+
+    #[test]
+    fn test_to_group() {
+        let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 4
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+        let obj = ObjFile::parse(input).unwrap();
+        let g = obj.to_group();
+        assert_eq!(g.len(), 3);
+        assert_eq!(obj.to_group().len(), 3);
+        assert_eq!(obj.triangle_counts().len(), 3);
+        let r = Ray::new(
+            TypedVec::point(-0.5, 0.5, -2f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let xs = g.intersect(r);
+        assert_eq!(xs.len(), 2);
+        assert!(xs[0].obj.parent().is_some());
+    }
+
+    #[test]
+    fn test_vertex_normals() {
+        let input = "vn 0 0 1
+vn 0.707 0 -0.707
+vn 1 2 3";
+        let obj = ObjFile::parse(input).unwrap();
+        assert_eq!(obj.normals[0], TypedVec::vector(0f64, 0f64, 1f64));
+        assert_eq!(obj.normals[1], TypedVec::vector(0.707, 0f64, -0.707));
+        assert_eq!(obj.normals[2], TypedVec::vector(1f64, 2f64, 3f64));
+    }
+
+    #[test]
+    fn test_faces_with_normals() {
+        let input = "v 0 1 0
+v -1 0 0
+v 1 0 0
+
+vt 0.5 1
+vt 0 0
+vt 1 0
+
+vn -1 0 0
+vn 1 0 0
+vn 0 1 0
+
+f 1//3 2//1 3//2
+f 1/1/3 2/2/1 3/3/2";
+        let obj = ObjFile::parse(input).unwrap();
+        assert_eq!(obj.texture_coords.len(), 3);
+        let t = obj.smooth_triangles(None).unwrap();
+        assert_eq!(t.len(), 2);
+        assert_eq!(t[0].p1, obj.vertices[0]);
+        assert_eq!(t[0].n1, obj.normals[2]);
+        assert_eq!(t[0].n2, obj.normals[0]);
+        assert_eq!(t[0].n3, obj.normals[1]);
+        assert_eq!(t[1], t[0]);
+    }
+
+    #[test]
+    fn test_negative_indices() {
+        let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+f -3 -2 -1";
+        let obj = ObjFile::parse(input).unwrap();
+        let t = obj.triangles(None).unwrap();
+        assert_eq!(t[0].p1, obj.vertices[0]);
+        assert_eq!(t[0].p3, obj.vertices[2]);
+    }
+
+    #[test]
+    fn test_malformed_records() {
+        let examples = vec![
+            ("v 1 2", 1),
+            ("v 1 two 3", 1),
+            ("v 1 2 3\nv 1 2 4\nf 1 2", 3),
+            ("v 1 2 3\nf 1 2 7", 2),
+            ("vn 1 0 0\nv 1 2 3\nf 1//2 1 1", 3),
+            ("v 1 2 3 4 5", 1),
+        ];
+        for (input, expected) in examples {
+            dbg!(&input);
+            match ObjFile::parse(input) {
+                Err(ObjError::Malformed { line, .. }) => assert_eq!(line, expected),
+                other => panic!("expected a malformed record, got {:?}", other),
+            }
+        }
+    }
+}
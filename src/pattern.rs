@@ -1,5 +1,6 @@
 use crate::colour::{Colour, WHITE};
-use crate::matrix::Matrix;
+use crate::matrix4::Matrix4;
+use crate::transform::Transform;
 use crate::vec3::TypedVec;
 use lazy_static::lazy_static;
 
@@ -19,14 +20,14 @@ pub struct Pattern {
     b: Colour,
     is: PatternType,
     perturb: bool,
-    pub transform: Option<Matrix<f64>>,
+    pub transform: Option<Transform>,
 }
 
 impl Default for Pattern {
     fn default() -> Self {
         Self {
             is: PatternType::None,
-            transform: Some(Matrix::identity(4)),
+            transform: Some(Matrix4::identity().into()),
             a: *WHITE,
             b: *WHITE,
             perturb: false,
@@ -105,12 +106,12 @@ impl Pattern {
         }
     }
 
-    pub fn transform(&self) -> &Option<Matrix<f64>> {
+    pub fn transform(&self) -> &Option<Transform> {
         &self.transform
     }
 
-    pub fn set_transform(&mut self, t: Matrix<f64>) {
-        self.transform = Some(t);
+    pub fn set_transform(&mut self, t: Matrix4) {
+        self.transform = Some(t.into());
     }
 
     fn checker_at(&self, point: TypedVec) -> Colour {
@@ -261,7 +262,7 @@ fn perlin_noise(x: f64, y: f64, z: f64) -> f64 {
 mod test {
     use crate::colour::*;
     use crate::hittable::HittableImpl;
-    use crate::matrix::Matrix;
+    use crate::matrix4::Matrix4;
     use crate::pattern::Pattern;
     use crate::pattern::PatternType::Stripe;
     use crate::sphere::Sphere;
@@ -269,8 +270,10 @@ mod test {
 
     #[test]
     fn test_pattern_object_transform() {
-        let mut shape = Sphere::default();
-        shape.transform = Some(Matrix::scaling(2.0, 2.0, 2.0));
+        let shape = Sphere {
+            transform: Some(Matrix4::scaling(2.0, 2.0, 2.0).into()),
+            ..Default::default()
+        };
         let pattern = Pattern::test_pattern();
         let c = shape.pattern_at(&pattern, TypedVec::point(2.0, 3.0, 4.0));
         assert_eq!(c.unwrap(), Colour::new(1.0, 1.5, 2.0))
@@ -280,17 +283,19 @@ mod test {
     fn test_pattern_pattern_transform() {
         let shape = Sphere::default();
         let mut pattern = Pattern::test_pattern();
-        pattern.transform = Some(Matrix::scaling(2.0, 2.0, 2.0));
+        pattern.transform = Some(Matrix4::scaling(2.0, 2.0, 2.0).into());
         let c = shape.pattern_at(&pattern, TypedVec::point(2.0, 3.0, 4.0));
         assert_eq!(c.unwrap(), Colour::new(1.0, 1.5, 2.0))
     }
 
     #[test]
     fn test_pattern_object_pattern_transform() {
-        let mut shape = Sphere::default();
-        shape.transform = Some(Matrix::scaling(2.0, 2.0, 2.0));
+        let shape = Sphere {
+            transform: Some(Matrix4::scaling(2.0, 2.0, 2.0).into()),
+            ..Default::default()
+        };
         let mut pattern = Pattern::test_pattern();
-        pattern.transform = Some(Matrix::translation(0.5, 1.0, 1.5));
+        pattern.transform = Some(Matrix4::translation(0.5, 1.0, 1.5).into());
         let c = shape.pattern_at(&pattern, TypedVec::point(2.5, 3.0, 3.5));
         assert_eq!(c.unwrap(), Colour::new(0.75, 0.5, 0.25))
     }
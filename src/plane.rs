@@ -1,7 +1,7 @@
+use crate::bounds::BoundingBox;
 use crate::hittable::HittableImpl;
 use crate::intersection::Intersection;
 use crate::material::Material;
-use crate::matrix::Matrix;
 use crate::ray::Ray;
 use crate::shape;
 use crate::vec3::TypedVec;
@@ -14,7 +14,7 @@ impl<'a> Plane<'a> {
         Ok(TypedVec::vector(0f64, 1f64, 0f64))
     }
 
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
         let mut ret = Vec::new();
         if ray.direction.y.zeroish() {
             return ret;
@@ -22,11 +22,19 @@ impl<'a> Plane<'a> {
         ret.push(Intersection::new(-ray.origin.y / ray.direction.y, self));
         ret
     }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            TypedVec::point(-f64::INFINITY, 0f64, -f64::INFINITY),
+            TypedVec::point(f64::INFINITY, 0f64, f64::INFINITY),
+        )
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::hittable::Hittable;
+    use crate::hittable::HittableImpl;
     use crate::plane::Plane;
     use crate::ray::Ray;
     use crate::vec3::TypedVec;
@@ -76,4 +84,11 @@ mod test {
         assert_eq!(xs.len(), 1);
         assert_eq!(xs[0].t, 1f64);
     }
+
+    #[test]
+    fn test_bounds() {
+        let b = Plane::default().bounds();
+        assert_eq!(b.min, TypedVec::point(-f64::INFINITY, 0f64, -f64::INFINITY));
+        assert_eq!(b.max, TypedVec::point(f64::INFINITY, 0f64, f64::INFINITY));
+    }
 }
@@ -0,0 +1,351 @@
+use crate::canvas::BitDepth;
+use anyhow::*;
+use std::io::Write;
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// `samples` holds the channels row by row, already scaled to `depth`.
+pub(crate) fn encode<W: Write>(
+    out: &mut W,
+    width: usize,
+    height: usize,
+    depth: BitDepth,
+    samples: &[u16],
+) -> Result<()> {
+    ensure!(
+        samples.len() == width * height * 3,
+        "expected {} samples, got {}",
+        width * height * 3,
+        samples.len()
+    );
+    ensure!(
+        width > 0 && height > 0 && width <= i32::MAX as usize && height <= i32::MAX as usize,
+        "PNG can't hold a {}x{} image",
+        width,
+        height
+    );
+
+    out.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // bit depth, colour type 2 (RGB), then the only defined compression and
+    // filter methods and no interlacing
+    header.extend_from_slice(&[(depth.bytes() * 8) as u8, 2, 0, 0, 0]);
+    chunk(out, b"IHDR", &header)?;
+
+    chunk(out, b"IDAT", &zlib(&filter(width, depth, samples)))?;
+    chunk(out, b"IEND", &[])?;
+    Ok(())
+}
+
+fn chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(crc32(0, kind), data);
+    out.write_all(&crc.to_be_bytes())?;
+    Ok(())
+}
+
+fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for block in data.chunks(5552) {
+        for byte in block {
+            a += u32::from(*byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// Each row gets whichever of the five PNG filters leaves the smallest
+// differences, the usual guess at what will compress best.
+fn filter(width: usize, depth: BitDepth, samples: &[u16]) -> Vec<u8> {
+    let bpp = 3 * depth.bytes();
+    let stride = width * bpp;
+    let bytes: Vec<u8> = match depth {
+        BitDepth::Eight => samples.iter().map(|s| *s as u8).collect(),
+        BitDepth::Sixteen => samples.iter().flat_map(|s| s.to_be_bytes()).collect(),
+    };
+
+    let mut out = Vec::with_capacity(bytes.len() + bytes.len() / stride);
+    let empty = vec![0u8; stride];
+    let mut candidate = vec![0u8; stride];
+    let mut best = vec![0u8; stride];
+    for (y, row) in bytes.chunks(stride).enumerate() {
+        let above = if y == 0 {
+            &empty[..]
+        } else {
+            &bytes[(y - 1) * stride..y * stride]
+        };
+        let mut best_kind = 0;
+        let mut best_cost = u64::MAX;
+        for kind in 0..5u8 {
+            for i in 0..stride {
+                let left = if i >= bpp { row[i - bpp] } else { 0 };
+                let up = above[i];
+                let corner = if i >= bpp { above[i - bpp] } else { 0 };
+                let predicted = match kind {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                    _ => paeth(left, up, corner),
+                };
+                candidate[i] = row[i].wrapping_sub(predicted);
+            }
+            let cost = candidate
+                .iter()
+                .map(|b| u64::from((*b as i8).unsigned_abs()))
+                .sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_kind = kind;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        out.push(best_kind);
+        out.extend_from_slice(&best);
+    }
+    out
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    // Deflate packs values from the least significant bit up.
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes go in most significant bit first.
+    fn write_code(&mut self, code: u32, count: u32) {
+        let reversed = code.reverse_bits() >> (32 - count);
+        self.write(reversed, count);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+const WINDOW: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+// How far back along the hash chain to look before settling for the best
+// match so far.
+const MAX_CHAIN: usize = 64;
+
+fn literal(bits: &mut BitWriter, value: u16) {
+    match value {
+        0..=143 => bits.write_code(0x30 + u32::from(value), 8),
+        144..=255 => bits.write_code(0x190 + u32::from(value - 144), 9),
+        256..=279 => bits.write_code(u32::from(value - 256), 7),
+        _ => bits.write_code(0xc0 + u32::from(value - 280), 8),
+    }
+}
+
+fn copy(bits: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE
+        .iter()
+        .rposition(|base| usize::from(*base) <= length)
+        .unwrap();
+    literal(bits, 257 + code as u16);
+    bits.write(
+        (length - usize::from(LENGTH_BASE[code])) as u32,
+        LENGTH_EXTRA[code],
+    );
+    let code = DISTANCE_BASE
+        .iter()
+        .rposition(|base| usize::from(*base) <= distance)
+        .unwrap();
+    bits.write_code(code as u32, 5);
+    bits.write(
+        (distance - usize::from(DISTANCE_BASE[code])) as u32,
+        DISTANCE_EXTRA[code],
+    );
+}
+
+fn hash(data: &[u8]) -> usize {
+    (usize::from(data[0]) << 10 ^ usize::from(data[1]) << 5 ^ usize::from(data[2])) & 0x7fff
+}
+
+fn insert(data: &[u8], head: &mut [usize], prev: &mut [usize], pos: usize) {
+    if pos + MIN_MATCH <= data.len() {
+        let h = hash(&data[pos..]);
+        prev[pos % WINDOW] = head[h];
+        head[h] = pos;
+    }
+}
+
+/// One fixed-Huffman deflate block with greedy hash-chain matching.
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut bits = BitWriter {
+        out: vec![0x78, 0x01],
+        bits: 0,
+        count: 0,
+    };
+    // final block, fixed codes
+    bits.write(1, 1);
+    bits.write(1, 2);
+
+    let mut head = vec![usize::MAX; 0x8000];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best = (0, 0);
+        if pos + MIN_MATCH <= data.len() {
+            let limit = (data.len() - pos).min(MAX_MATCH);
+            let mut candidate = head[hash(&data[pos..])];
+            let mut chain = 0;
+            while candidate != usize::MAX && pos - candidate <= WINDOW && chain < MAX_CHAIN {
+                let length = data[candidate..]
+                    .iter()
+                    .zip(&data[pos..pos + limit])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best.0 {
+                    best = (length, pos - candidate);
+                    if length == limit {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW];
+                // anything at or after `candidate` is from an older lap of
+                // the ring buffer
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best.0 >= MIN_MATCH {
+            copy(&mut bits, best.0, best.1);
+            for p in pos..pos + best.0 {
+                insert(data, &mut head, &mut prev, p);
+            }
+            pos += best.0;
+        } else {
+            literal(&mut bits, u16::from(data[pos]));
+            insert(data, &mut head, &mut prev, pos);
+            pos += 1;
+        }
+    }
+    literal(&mut bits, 256);
+
+    let mut out = bits.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use crate::canvas::BitDepth;
+    use crate::png::{adler32, crc32, encode};
+
+    #[test]
+    fn test_checksums() {
+        let examples = vec![
+            ("empty", &b""[..], 0, 1),
+            ("iend", &b"IEND"[..], 0xae42_6082, 0x02d7_0121),
+            (
+                "fox",
+                &b"The quick brown fox jumps over the lazy dog"[..],
+                0x414f_a339,
+                0x5bdc_0fda,
+            ),
+        ];
+        for (name, data, crc, adler) in examples {
+            dbg!(&name);
+            assert_eq!(crc32(0, data), crc);
+            assert_eq!(adler32(data), adler);
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let mut out = Vec::new();
+        let samples = vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 128, 128, 128];
+        encode(&mut out, 2, 2, BitDepth::Eight, &samples).unwrap();
+        assert_eq!(&out[..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
+        assert_eq!(&out[8..16], &[0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(&out[16..29], &[0, 0, 0, 2, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+        assert_eq!(&out[37..41], b"IDAT");
+        assert_eq!(
+            &out[out.len() - 12..],
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+
+        let mut out = Vec::new();
+        encode(&mut out, 2, 2, BitDepth::Sixteen, &samples).unwrap();
+        assert_eq!(out[24], 16);
+        assert!(encode(&mut out, 3, 2, BitDepth::Eight, &samples).is_err());
+        assert!(encode(&mut out, 0, 0, BitDepth::Eight, &[]).is_err());
+    }
+}
@@ -0,0 +1,325 @@
+use crate::canvas::BitDepth;
+use crate::colour::Colour;
+use anyhow::*;
+use std::io::{BufWriter, Write};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd)]
+pub enum PpmFormat {
+    Ascii,
+    /// Big-endian when samples take two bytes.
+    Binary,
+}
+
+// Text lines in a PPM shouldn't run past 70 characters.
+const MAX_LINE: usize = 70;
+
+/// Streams `pixels` out one at a time.
+pub(crate) fn encode<W: Write>(
+    out: W,
+    width: usize,
+    height: usize,
+    format: PpmFormat,
+    depth: BitDepth,
+    pixels: impl IntoIterator<Item = [u16; 3]>,
+) -> Result<()> {
+    let mut out = BufWriter::new(out);
+    let magic = match format {
+        PpmFormat::Ascii => "P3",
+        PpmFormat::Binary => "P6",
+    };
+    write!(out, "{}\n{} {}\n{}\n", magic, width, height, depth.max())?;
+
+    let mut pixels = pixels.into_iter();
+    match format {
+        PpmFormat::Ascii => {
+            // room for the widest pixel, e.g. "255 255 255 "
+            let widest = 3 * (depth.max().to_string().len() + 1);
+            for _ in 0..height {
+                let mut line = 0;
+                for _ in 0..width {
+                    let [red, green, blue] =
+                        pixels.next().ok_or_else(|| anyhow!("ran out of pixels"))?;
+                    if line + widest >= MAX_LINE {
+                        out.write_all(b"\n")?;
+                        line = 0;
+                    }
+                    let text = format!("{} {} {} ", red, green, blue);
+                    out.write_all(text.as_bytes())?;
+                    line += text.len();
+                }
+                out.write_all(b"\n")?;
+            }
+            out.write_all(b"\n")?;
+        }
+        PpmFormat::Binary => {
+            for _ in 0..width * height {
+                let pixel = pixels.next().ok_or_else(|| anyhow!("ran out of pixels"))?;
+                for sample in pixel.iter() {
+                    match depth {
+                        BitDepth::Eight => out.write_all(&[*sample as u8])?,
+                        BitDepth::Sixteen => out.write_all(&sample.to_be_bytes())?,
+                    }
+                }
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// Walks through a PPM's header (and a P3's samples), which are whitespace
+// separated decimal numbers with `#` comments running to the end of a line.
+struct Tokens<'d> {
+    data: &'d [u8],
+    pos: usize,
+}
+
+impl<'d> Tokens<'d> {
+    fn skip_space(&mut self) {
+        while let Some(b) = self.data.get(self.pos) {
+            match b {
+                b'#' => {
+                    while self
+                        .data
+                        .get(self.pos)
+                        .is_some_and(|b| *b != b'\n' && *b != b'\r')
+                    {
+                        self.pos += 1;
+                    }
+                }
+                b if b.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn number(&mut self, what: &str) -> Result<usize> {
+        self.skip_space();
+        let start = self.pos;
+        while self.data.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return match self.data.get(self.pos) {
+                None => Err(anyhow!("ran out of data reading the {}", what)),
+                Some(b) => Err(anyhow!("expected the {}, found {:?}", what, *b as char)),
+            };
+        }
+        std::str::from_utf8(&self.data[start..self.pos])?
+            .parse()
+            .map_err(|e| anyhow!("bad {}: {}", what, e))
+    }
+}
+
+/// Channels are scaled from `[0, max]` to `[0, 1]`.
+pub(crate) fn decode(data: &[u8]) -> Result<(usize, usize, Vec<Colour>)> {
+    let format = match data.get(..2) {
+        Some(b"P3") => PpmFormat::Ascii,
+        Some(b"P6") => PpmFormat::Binary,
+        _ => return Err(anyhow!("not a P3 or P6 PPM")),
+    };
+    let mut tokens = Tokens { data, pos: 2 };
+    if !tokens.data.get(2).is_some_and(u8::is_ascii_whitespace) {
+        return Err(anyhow!("expected whitespace after the magic number"));
+    }
+    let width = tokens.number("width")?;
+    let height = tokens.number("height")?;
+    let max = tokens.number("maximum value")?;
+    ensure!(
+        (1..=65535).contains(&max),
+        "maximum value {} is outside 1 to 65535",
+        max
+    );
+    let count = width
+        .checked_mul(height)
+        .and_then(|count| count.checked_mul(3).map(|_| count))
+        .ok_or_else(|| anyhow!("{}x{} is too big", width, height))?;
+    let scale = max as f64;
+
+    let samples: Vec<usize> = match format {
+        PpmFormat::Ascii => (0..count * 3)
+            .map(|_| tokens.number("sample"))
+            .collect::<Result<_>>()?,
+        PpmFormat::Binary => {
+            // exactly one whitespace character separates the header from
+            // the samples, which might themselves look like whitespace
+            match data.get(tokens.pos) {
+                Some(b) if b.is_ascii_whitespace() => {}
+                _ => return Err(anyhow!("expected whitespace after the maximum value")),
+            }
+            let start = tokens.pos + 1;
+            let size = if max > 255 { 2 } else { 1 };
+            let len = count
+                .checked_mul(3 * size)
+                .ok_or_else(|| anyhow!("{}x{} is too big", width, height))?;
+            let found = data.len() - start;
+            ensure!(
+                len <= found,
+                "pixel data is truncated: expected {} bytes, found {}",
+                len,
+                found
+            );
+            data[start..start + len]
+                .chunks(size)
+                .map(|c| c.iter().fold(0, |acc, b| acc << 8 | usize::from(*b)))
+                .collect()
+        }
+    };
+    if let Some(s) = samples.iter().find(|s| **s > max) {
+        return Err(anyhow!("sample {} is above the maximum of {}", s, max));
+    }
+
+    let pixels = samples
+        .chunks(3)
+        .map(|c| {
+            Colour::new(
+                c[0] as f64 / scale,
+                c[1] as f64 / scale,
+                c[2] as f64 / scale,
+            )
+        })
+        .collect();
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::canvas::BitDepth;
+    use crate::colour::Colour;
+    use crate::ppm::{decode, encode, PpmFormat};
+
+    #[test]
+    fn test_encode() {
+        let pixels = vec![[255, 0, 0], [0, 128, 0], [0, 0, 255], [1, 2, 255]];
+        let examples: Vec<(&str, PpmFormat, BitDepth, &[u8])> = vec![
+            (
+                "ascii",
+                PpmFormat::Ascii,
+                BitDepth::Eight,
+                b"P3\n2 2\n255\n255 0 0 0 128 0 \n0 0 255 1 2 255 \n\n",
+            ),
+            (
+                "ascii sixteen",
+                PpmFormat::Ascii,
+                BitDepth::Sixteen,
+                b"P3\n2 2\n65535\n255 0 0 0 128 0 \n0 0 255 1 2 255 \n\n",
+            ),
+            (
+                "binary",
+                PpmFormat::Binary,
+                BitDepth::Eight,
+                b"P6\n2 2\n255\n\xff\x00\x00\x00\x80\x00\x00\x00\xff\x01\x02\xff",
+            ),
+            (
+                "binary sixteen",
+                PpmFormat::Binary,
+                BitDepth::Sixteen,
+                b"P6\n2 2\n65535\n\x00\xff\x00\x00\x00\x00\x00\x00\x00\x80\x00\x00\
+                  \x00\x00\x00\x00\x00\xff\x00\x01\x00\x02\x00\xff",
+            ),
+        ];
+        for (name, format, depth, expected) in examples {
+            dbg!(&name);
+            let mut out = Vec::new();
+            encode(&mut out, 2, 2, format, depth, pixels.clone()).unwrap();
+            assert_eq!(out, expected);
+        }
+        assert!(encode(Vec::new(), 3, 2, PpmFormat::Binary, BitDepth::Eight, pixels).is_err());
+    }
+
+    #[test]
+    fn test_line_length() {
+        let examples = vec![("eight", BitDepth::Eight), ("sixteen", BitDepth::Sixteen)];
+        for (name, depth) in examples {
+            dbg!(&name);
+            let max = depth.max();
+            let mut out = Vec::new();
+            let pixels = (0..40).map(|_| [max, max, max]);
+            encode(&mut out, 20, 2, PpmFormat::Ascii, depth, pixels).unwrap();
+            let text = String::from_utf8(out).unwrap();
+            assert!(text.lines().all(|l| l.len() < 70));
+            assert_eq!(
+                text.split_whitespace().count(),
+                4 + 3 * 40,
+                "every sample is written"
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let red = Colour::new(1.0, 0.0, 0.0);
+        let grey = Colour::new(0.2, 0.2, 0.2);
+        let examples: Vec<(&str, &[u8], Vec<Colour>)> = vec![
+            (
+                "ascii",
+                b"P3\n2 1\n255\n255 0 0 51 51 51\n",
+                vec![red, grey],
+            ),
+            (
+                "comments and odd spacing",
+                b"P3 # made by hand\r\n#size next\n 2\t1 # wide\n\n5\n5 0 0 #red\n1\n 1 1",
+                vec![red, grey],
+            ),
+            (
+                "ascii sixteen",
+                b"P3\n1 1\n1000\n0 1000 200\n",
+                vec![Colour::new(0.0, 1.0, 0.2)],
+            ),
+            (
+                "binary",
+                b"P6 2 1 255\n\xff\x00\x00\x33\x33\x33",
+                vec![red, grey],
+            ),
+            (
+                "binary starting with whitespace bytes",
+                b"P6\n1 1\n# comment\n10\n\x0a\x0a\x02",
+                vec![Colour::new(1.0, 1.0, 0.2)],
+            ),
+            (
+                "binary sixteen",
+                b"P6\n1 1\n65535\n\xff\xff\x00\x00\x80\x00",
+                vec![Colour::new(1.0, 0.0, 32768.0 / 65535.0)],
+            ),
+        ];
+        for (name, data, pixels) in examples {
+            dbg!(&name);
+            let (width, height, out) = decode(data).unwrap();
+            assert_eq!((width, height), (pixels.len(), 1));
+            let round = |p: Vec<Colour>| p.iter().map(|c| c.round(100000f64)).collect::<Vec<_>>();
+            assert_eq!(round(out), round(pixels));
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let examples: Vec<(&str, &[u8])> = vec![
+            ("empty", b""),
+            ("wrong magic", b"P5\n1 1\n255\n\x00"),
+            ("no space after magic", b"P31 1\n255\n0 0 0"),
+            ("missing height", b"P3\n1\n"),
+            ("letters", b"P3\n1 x\n255\n"),
+            ("zero maximum", b"P3\n1 1\n0\n0 0 0"),
+            ("huge maximum", b"P3\n1 1\n65536\n0 0 0"),
+            ("too few samples", b"P3\n2 1\n255\n0 0 0 0 0"),
+            ("sample too big", b"P3\n1 1\n100\n0 101 0"),
+            ("truncated binary", b"P6\n2 1\n255\n\x00\x00\x00\x00"),
+            ("truncated sixteen", b"P6\n1 1\n65535\n\x00\x00\x00\x00\x00"),
+            ("binary no space", b"P6\n1 1\n255"),
+            ("binary sample too big", b"P6\n1 1\n100\n\x00\x65\x00"),
+            ("overflowing size", b"P6\n18446744073709551615 2\n255\n"),
+            (
+                "overflowing byte count",
+                b"P6\n3074457345618258602 2\n65535\n",
+            ),
+            (
+                "overflowing sample count",
+                b"P3\n4294967296 2147483648\n255\n1 2 3",
+            ),
+        ];
+        for (name, data) in examples {
+            dbg!(&name);
+            assert!(decode(data).is_err());
+        }
+    }
+}
@@ -1,32 +1,42 @@
-use crate::matrix::Matrix;
+use crate::matrix4::Matrix4;
 use crate::vec3::TypedVec;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Ray {
     pub origin: TypedVec,
     pub direction: TypedVec,
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(origin: TypedVec, direction: TypedVec) -> Self {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            time: 0f64,
+        }
+    }
+
+    pub fn with_time(self, time: f64) -> Self {
+        Self { time, ..self }
     }
 
     pub fn position(&self, time: f64) -> TypedVec {
         self.origin + self.direction * time
     }
 
-    pub(crate) fn transform(&self, transform: &'_ Matrix<f64>) -> Self {
+    pub(crate) fn transform(&self, transform: &'_ Matrix4) -> Self {
         Self {
-            direction: { transform.clone() * self.direction },
+            direction: { *transform * self.direction },
             origin: { transform * self.origin },
+            time: self.time,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::matrix::Matrix;
+    use crate::matrix4::Matrix4;
     use crate::ray::Ray;
     use crate::vec3::TypedVec;
 
@@ -46,7 +56,7 @@ mod tests {
             TypedVec::point(1f64, 2f64, 3f64),
             TypedVec::vector(0f64, 1f64, 0f64),
         );
-        let m = Matrix::translation(3f64, 4f64, 5f64);
+        let m = Matrix4::translation(3f64, 4f64, 5f64);
         let out = r.transform(&m);
         assert_eq!(out.origin, TypedVec::point(4f64, 6f64, 8f64));
         assert_eq!(out.direction, TypedVec::vector(0f64, 1f64, 0f64));
@@ -58,9 +68,21 @@ mod tests {
             TypedVec::point(1f64, 2f64, 3f64),
             TypedVec::vector(0f64, 1f64, 0f64),
         );
-        let m = Matrix::scaling(2f64, 3f64, 4f64);
+        let m = Matrix4::scaling(2f64, 3f64, 4f64);
         let out = r.transform(&m);
         assert_eq!(out.origin, TypedVec::point(2f64, 6f64, 12f64));
         assert_eq!(out.direction, TypedVec::vector(0f64, 3f64, 0f64));
     }
+
+    #[test]
+    fn test_time() {
+        let r = Ray::new(
+            TypedVec::point(1f64, 2f64, 3f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+        );
+        assert_eq!(r.time, 0f64);
+        let r = r.with_time(0.25);
+        assert_eq!(r.time, 0.25);
+        assert_eq!(r.transform(&Matrix4::scaling(2f64, 3f64, 4f64)).time, 0.25);
+    }
 }
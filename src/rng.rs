@@ -0,0 +1,78 @@
+/// SplitMix64, so renders come out the same every time.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn from_keys(keys: &[u64]) -> Self {
+        let mut rng = Self::new(0);
+        for k in keys {
+            rng.state = rng.next_u64() ^ *k;
+        }
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+        assert_eq!(
+            Rng::from_keys(&[3, 4]).next_u64(),
+            Rng::from_keys(&[3, 4]).next_u64()
+        );
+        assert_ne!(
+            Rng::from_keys(&[3, 4]).next_u64(),
+            Rng::from_keys(&[4, 3]).next_u64()
+        );
+    }
+
+    #[test]
+    fn test_adjacent_keys() {
+        let examples = vec![
+            ("first", [0, 0, 0], [1, 0, 0]),
+            ("middle", [7, 3, 4], [7, 4, 4]),
+            ("last", [7, 3, 4], [7, 3, 5]),
+            ("swapped", [7, 3, 4], [7, 4, 3]),
+        ];
+        for (name, a, b) in examples {
+            dbg!(&name);
+            assert_ne!(Rng::from_keys(&a).next_u64(), Rng::from_keys(&b).next_u64());
+        }
+    }
+
+    #[test]
+    fn test_unit_range() {
+        let mut r = Rng::new(7);
+        for _ in 0..1000 {
+            let f = r.next_f64();
+            assert!((0f64..1f64).contains(&f));
+        }
+    }
+}
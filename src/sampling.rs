@@ -0,0 +1,368 @@
+use crate::colour::Colour;
+use crate::rng::Rng;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub enum SamplePattern {
+    #[default]
+    Grid,
+    Jittered,
+    /// One sample per row and column of an `n² * n²` grid.
+    Stratified,
+}
+
+/// How much each sample counts towards its own pixel, by how far it is from
+/// the middle. Samples never spill into neighbouring pixels.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub enum Filter {
+    #[default]
+    Box,
+    Tent,
+    /// Mitchell-Netravali with B = C = 1/3.
+    Mitchell,
+}
+
+impl Filter {
+    /// Zero outside the pixel, more than 0.5 from the middle either way.
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    fn weight_1d(&self, d: f64) -> f64 {
+        let d = d.abs();
+        if d > 0.5 {
+            return 0f64;
+        }
+        match self {
+            Filter::Box => 1f64,
+            Filter::Tent => 1f64 - d,
+            // only the central lobe falls inside the pixel
+            Filter::Mitchell => {
+                let (b, c) = (1f64 / 3f64, 1f64 / 3f64);
+                ((12f64 - 9f64 * b - 6f64 * c) * d.powi(3)
+                    + (-18f64 + 12f64 * b + 6f64 * c) * d.powi(2)
+                    + (6f64 - 2f64 * b))
+                    / 6f64
+            }
+        }
+    }
+}
+
+/// How many rays go through each pixel and how they're combined.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Supersampling {
+    pub n: usize,
+    pub pattern: SamplePattern,
+    pub filter: Filter,
+    /// The same seed always renders the same image.
+    pub seed: u64,
+}
+
+impl Default for Supersampling {
+    fn default() -> Self {
+        Self {
+            n: 1,
+            pattern: SamplePattern::Grid,
+            filter: Filter::Box,
+            seed: 0,
+        }
+    }
+}
+
+impl Supersampling {
+    pub fn new(n: usize, pattern: SamplePattern, filter: Filter) -> Self {
+        Self {
+            n,
+            pattern,
+            filter,
+            ..Default::default()
+        }
+    }
+
+    pub fn samples(&self) -> usize {
+        self.n * self.n
+    }
+
+    /// Offsets from the middle of pixel (`x`, `y`) with their weights.
+    pub fn offsets(&self, x: usize, y: usize) -> Vec<(f64, f64, f64)> {
+        let n = self.n.max(1);
+        let mut rng = Rng::from_keys(&[self.seed, x as u64, y as u64]);
+        let unit: Vec<(f64, f64)> = match self.pattern {
+            SamplePattern::Grid => (0..n * n)
+                .map(|i| {
+                    (
+                        ((i % n) as f64 + 0.5) / n as f64,
+                        ((i / n) as f64 + 0.5) / n as f64,
+                    )
+                })
+                .collect(),
+            SamplePattern::Jittered => (0..n * n)
+                .map(|i| {
+                    (
+                        ((i % n) as f64 + rng.next_f64()) / n as f64,
+                        ((i / n) as f64 + rng.next_f64()) / n as f64,
+                    )
+                })
+                .collect(),
+            SamplePattern::Stratified => {
+                let count = n * n;
+                let mut rows: Vec<usize> = (0..count).collect();
+                for i in (1..count).rev() {
+                    let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+                    rows.swap(i, j);
+                }
+                (0..count)
+                    .map(|i| {
+                        (
+                            (i as f64 + rng.next_f64()) / count as f64,
+                            (rows[i] as f64 + rng.next_f64()) / count as f64,
+                        )
+                    })
+                    .collect()
+            }
+        };
+        unit.into_iter()
+            .map(|(u, v)| {
+                let (dx, dy) = (u - 0.5, v - 0.5);
+                (dx, dy, self.filter.weight(dx, dy))
+            })
+            .collect()
+    }
+}
+
+/// Shirley and Chiu's concentric mapping onto the unit disk.
+pub fn sample_disk(u: f64, v: f64) -> (f64, f64) {
+    let (a, b) = (2f64 * u - 1f64, 2f64 * v - 1f64);
+    if a == 0f64 && b == 0f64 {
+        return (0f64, 0f64);
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, FRAC_PI_4 * (b / a))
+    } else {
+        (b, FRAC_PI_2 - FRAC_PI_4 * (a / b))
+    };
+    (r * theta.cos(), r * theta.sin())
+}
+
+/// Refines pixels that stand out from their neighbours after a first pass.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Adaptive {
+    /// The largest per-channel difference that still counts as smooth.
+    pub threshold: f64,
+    pub max_depth: usize,
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Self {
+            threshold: 0.1,
+            max_depth: 2,
+        }
+    }
+}
+
+impl Adaptive {
+    pub fn new(threshold: f64, max_depth: usize) -> Self {
+        Self {
+            threshold,
+            max_depth,
+        }
+    }
+
+    pub fn exceeds(&self, colours: &[Colour]) -> bool {
+        colours.iter().enumerate().any(|(i, a)| {
+            colours[i + 1..]
+                .iter()
+                .any(|b| contrast(*a, *b) > self.threshold)
+        })
+    }
+}
+
+pub fn contrast(a: Colour, b: Colour) -> f64 {
+    (a.red - b.red)
+        .abs()
+        .max((a.green - b.green).abs())
+        .max((a.blue - b.blue).abs())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::colour::Colour;
+    use crate::rng::Rng;
+    use crate::roundf;
+    use crate::sampling::{contrast, sample_disk, Adaptive, Filter, SamplePattern, Supersampling};
+
+    #[test]
+    fn test_default_is_pixel_centre() {
+        let s = Supersampling::default();
+        assert_eq!(s.offsets(3, 4), vec![(0f64, 0f64, 1f64)]);
+    }
+
+    #[test]
+    fn test_grid_offsets() {
+        let s = Supersampling::new(2, SamplePattern::Grid, Filter::Box);
+        assert_eq!(
+            s.offsets(0, 0),
+            vec![
+                (-0.25, -0.25, 1f64),
+                (0.25, -0.25, 1f64),
+                (-0.25, 0.25, 1f64),
+                (0.25, 0.25, 1f64),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_offsets_inside_pixel() {
+        let examples = vec![
+            ("box", Filter::Box),
+            ("tent", Filter::Tent),
+            ("mitchell", Filter::Mitchell),
+        ];
+        for (name, filter) in examples {
+            dbg!(&name);
+            for pattern in [
+                SamplePattern::Grid,
+                SamplePattern::Jittered,
+                SamplePattern::Stratified,
+            ] {
+                for n in 1..5 {
+                    let offsets = Supersampling::new(n, pattern, filter).offsets(3, 7);
+                    assert_eq!(offsets.len(), n * n);
+                    for (dx, dy, w) in offsets {
+                        assert!(dx.abs() <= 0.5 && dy.abs() <= 0.5);
+                        assert!(w > 0f64);
+                    }
+                }
+            }
+        }
+        let s = Supersampling::new(2, SamplePattern::Grid, Filter::Mitchell);
+        let offsets: Vec<_> = s.offsets(0, 0).iter().map(|(x, y, _)| (*x, *y)).collect();
+        assert_eq!(
+            offsets,
+            vec![(-0.25, -0.25), (0.25, -0.25), (-0.25, 0.25), (0.25, 0.25)]
+        );
+    }
+
+    #[test]
+    fn test_jittered_offsets_stay_in_cells() {
+        let s = Supersampling::new(4, SamplePattern::Jittered, Filter::Box);
+        let offsets = s.offsets(10, 20);
+        assert_eq!(offsets.len(), 16);
+        for (i, (dx, dy, w)) in offsets.iter().enumerate() {
+            let (cx, cy) = ((i % 4) as f64, (i / 4) as f64);
+            assert!(*dx >= cx * 0.25 - 0.5 && *dx < (cx + 1f64) * 0.25 - 0.5);
+            assert!(*dy >= cy * 0.25 - 0.5 && *dy < (cy + 1f64) * 0.25 - 0.5);
+            assert_eq!(*w, 1f64);
+        }
+    }
+
+    #[test]
+    fn test_stratified_one_per_row_and_column() {
+        let s = Supersampling::new(3, SamplePattern::Stratified, Filter::Box);
+        let offsets = s.offsets(1, 2);
+        let mut cols: Vec<usize> = offsets
+            .iter()
+            .map(|(dx, _, _)| ((dx + 0.5) * 9f64) as usize)
+            .collect();
+        let mut rows: Vec<usize> = offsets
+            .iter()
+            .map(|(_, dy, _)| ((dy + 0.5) * 9f64) as usize)
+            .collect();
+        cols.sort();
+        rows.sort();
+        assert_eq!(cols, (0..9).collect::<Vec<usize>>());
+        assert_eq!(rows, (0..9).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_seeded_offsets_reproducible() {
+        let mut s = Supersampling::new(4, SamplePattern::Jittered, Filter::Tent);
+        s.seed = 99;
+        assert_eq!(s.offsets(5, 6), s.offsets(5, 6));
+        assert_ne!(s.offsets(5, 6), s.offsets(6, 5));
+        let mut other = s;
+        other.seed = 100;
+        assert_ne!(s.offsets(5, 6), other.offsets(5, 6));
+    }
+
+    #[test]
+    fn test_filter_weights() {
+        let examples = vec![
+            ("box centre", Filter::Box, 0f64, 1f64),
+            ("box edge", Filter::Box, 0.5, 1f64),
+            ("box outside", Filter::Box, 0.6, 0f64),
+            ("tent centre", Filter::Tent, 0f64, 1f64),
+            ("tent half", Filter::Tent, 0.5, 0.5),
+            ("tent outside", Filter::Tent, 0.6, 0f64),
+            ("mitchell centre", Filter::Mitchell, 0f64, 0.79012),
+            ("mitchell edge", Filter::Mitchell, 0.5, 0.47531),
+            ("mitchell outside", Filter::Mitchell, 0.6, 0f64),
+        ];
+        for (name, f, d, expected) in examples {
+            dbg!(&name);
+            assert_eq!(roundf(f.weight(d, 0f64), 100000f64), expected);
+        }
+    }
+
+    #[test]
+    fn test_contrast() {
+        let examples = vec![
+            (
+                "same",
+                Colour::new(0.5, 0.5, 0.5),
+                Colour::new(0.5, 0.5, 0.5),
+                0f64,
+            ),
+            (
+                "red",
+                Colour::new(0.2, 0.5, 0.5),
+                Colour::new(0.5, 0.5, 0.5),
+                0.3,
+            ),
+            (
+                "widest",
+                Colour::new(1f64, 0f64, 0.5),
+                Colour::new(0.9, 0.6, 0.5),
+                0.6,
+            ),
+        ];
+        for (name, a, b, expected) in examples {
+            dbg!(&name);
+            assert_eq!(roundf(contrast(a, b), 100000f64), expected);
+            assert_eq!(roundf(contrast(b, a), 100000f64), expected);
+        }
+    }
+
+    #[test]
+    fn test_adaptive_exceeds() {
+        let a = Adaptive::new(0.25, 2);
+        let grey = Colour::new(0.5, 0.5, 0.5);
+        assert!(!a.exceeds(&[]));
+        assert!(!a.exceeds(&[grey, grey, Colour::new(0.7, 0.5, 0.3)]));
+        assert!(a.exceeds(&[grey, grey, Colour::new(0.5, 0.8, 0.5)]));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn test_sample_disk() {
+        let examples = vec![
+            ("centre", (0.5, 0.5), (0f64, 0f64)),
+            ("right", (1f64, 0.5), (1f64, 0f64)),
+            ("top", (0.5, 1f64), (0f64, 1f64)),
+            ("left", (0f64, 0.5), (-1f64, 0f64)),
+            ("corner", (1f64, 1f64), (0.70711, 0.70711)),
+        ];
+        for (name, (u, v), expected) in examples {
+            dbg!(&name);
+            let (x, y) = sample_disk(u, v);
+            assert_eq!((roundf(x, 100000f64), roundf(y, 100000f64)), expected);
+        }
+
+        let mut rng = Rng::new(3);
+        for _ in 0..1000 {
+            let (x, y) = sample_disk(rng.next_f64(), rng.next_f64());
+            assert!(x * x + y * y <= 1f64 + 1e-12);
+        }
+    }
+}
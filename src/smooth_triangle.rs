@@ -0,0 +1,146 @@
+use crate::bounds::BoundingBox;
+use crate::hittable::HittableImpl;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shape;
+use crate::triangle::moller_trumbore;
+use crate::vec3::TypedVec;
+use anyhow::Result;
+
+shape!(
+    SmoothTriangle,
+    uv,
+    p1 -> TypedVec,
+    p2 -> TypedVec,
+    p3 -> TypedVec,
+    n1 -> TypedVec,
+    n2 -> TypedVec,
+    n3 -> TypedVec,
+    e1 -> TypedVec,
+    e2 -> TypedVec,
+    normal -> TypedVec
+);
+
+impl<'a> SmoothTriangle<'a> {
+    pub fn new(
+        p1: TypedVec,
+        p2: TypedVec,
+        p3: TypedVec,
+        n1: TypedVec,
+        n2: TypedVec,
+        n3: TypedVec,
+    ) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1,
+            e2,
+            normal: e2.cross_product(e1).normalize(),
+            material: Material::default(),
+            parent: None,
+            transform: None,
+            motion: None,
+        }
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
+        match moller_trumbore(self.p1, self.e1, self.e2, ray) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, self, u, v)],
+            None => vec![],
+        }
+    }
+
+    // Without a hit there's nothing to interpolate with, so fall back to the
+    // face normal.
+    fn local_normal_at(&self, _: TypedVec) -> Result<TypedVec> {
+        Ok(self.normal)
+    }
+
+    fn local_normal_at_hit(&self, _: TypedVec, hit: &Intersection) -> Result<TypedVec> {
+        Ok(self.n2 * hit.u + self.n3 * hit.v + self.n1 * (1f64 - hit.u - hit.v))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        bounds.add_point(self.p1);
+        bounds.add_point(self.p2);
+        bounds.add_point(self.p3);
+        bounds
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hittable::{Hittable, HittableImpl};
+    use crate::intersection::{Intersection, Intersections};
+    use crate::ray::Ray;
+    use crate::smooth_triangle::SmoothTriangle;
+    use crate::vec3::TypedVec;
+
+    fn triangle<'a>() -> SmoothTriangle<'a> {
+        SmoothTriangle::new(
+            TypedVec::point(0f64, 1f64, 0f64),
+            TypedVec::point(-1f64, 0f64, 0f64),
+            TypedVec::point(1f64, 0f64, 0f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+            TypedVec::vector(-1f64, 0f64, 0f64),
+            TypedVec::vector(1f64, 0f64, 0f64),
+        )
+    }
+
+    #[test]
+    fn test_face_normal() {
+        let t = triangle();
+        assert_eq!(t.normal, TypedVec::vector(0f64, 0f64, -1f64));
+        assert_eq!(
+            t.local_normal_at(TypedVec::point(0f64, 0.5, 0f64)).unwrap(),
+            t.normal
+        );
+    }
+
+    #[test]
+    fn test_intersection_stores_uv() {
+        let t = triangle();
+        let r = Ray::new(
+            TypedVec::point(-0.2, 0.3, -2f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let xs = t.intersect(r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(crate::roundf(xs[0].u, 100f64), 0.45);
+        assert_eq!(crate::roundf(xs[0].v, 100f64), 0.25);
+    }
+
+    #[test]
+    fn test_interpolated_normal() {
+        let t = triangle();
+        let i = Intersection::with_uv(1f64, &t, 0.45, 0.25);
+        let n = t
+            .normal_at_hit(TypedVec::point(0f64, 0f64, 0f64), &i)
+            .unwrap();
+        assert_eq!(n.round(100000f64), TypedVec::vector(-0.5547, 0.83205, 0f64));
+    }
+
+    #[test]
+    fn test_precompute_interpolates() {
+        let t = triangle();
+        let i = Intersection::with_uv(1f64, &t, 0.45, 0.25);
+        let r = Ray::new(
+            TypedVec::point(-0.2, 0.3, -2f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let xs = Intersections::from_iter(vec![i.clone()]);
+        let comps = i.precompute(r, &xs);
+        assert_eq!(
+            comps.normalv.round(100000f64),
+            TypedVec::vector(-0.5547, 0.83205, 0f64)
+        );
+    }
+}
@@ -1,7 +1,8 @@
+use crate::bounds::BoundingBox;
 use crate::hittable::HittableImpl;
 use crate::intersection::Intersection;
 use crate::material::Material;
-use crate::matrix::Matrix;
+use crate::matrix4::Matrix4;
 use crate::ray::Ray;
 use crate::shape;
 use crate::vec3::TypedVec;
@@ -17,17 +18,16 @@ impl<'a> Sphere<'a> {
 
     pub fn glass() -> Self {
         Self {
-            material: {
-                let mut m = Material::default();
-                m.transparency = 1.0;
-                m.refractive_index = 1.5;
-                m
+            material: Material {
+                transparency: 1.0,
+                refractive_index: 1.5,
+                ..Default::default()
             },
             ..Default::default()
         }
     }
 
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
         let mut ret = vec![];
         let sphere_to_ray = ray.origin - TypedVec::point(0.0, 0.0, 0.0);
         let a: f64 = ray.direction.dot_product(ray.direction);
@@ -43,20 +43,18 @@ impl<'a> Sphere<'a> {
     }
 
     fn local_normal_at(&self, p: TypedVec) -> Result<TypedVec> {
-        let c = TypedVec::point(0f64, 0f64, 0f64);
-        if let Some(transform) = &self.transform {
-            let object_point = transform.inverse()? * p;
-            let object_normal = object_point - c;
-            let mut world_normal = transform.inverse()?.transpose() * object_normal;
-            world_normal.w = 0f64;
-            Ok(world_normal.normalize())
-        } else {
-            Ok((p - c).normalize())
-        }
+        Ok(p - TypedVec::point(0f64, 0f64, 0f64))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            TypedVec::point(-1f64, -1f64, -1f64),
+            TypedVec::point(1f64, 1f64, 1f64),
+        )
     }
 
-    pub fn set_transform(&mut self, transform: Matrix<f64>) {
-        self.transform = Some(transform);
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Some(transform.into());
     }
 
     pub fn set_material(&mut self, material: Material) {
@@ -66,9 +64,11 @@ impl<'a> Sphere<'a> {
 
 #[cfg(test)]
 mod test {
+    use crate::bounds::BoundingBox;
     use crate::colour::*;
     use crate::hittable::{Hittable, HittableImpl};
-    use crate::matrix::{Axis, Matrix};
+    use crate::matrix::Axis;
+    use crate::matrix4::Matrix4;
     use crate::pattern::Pattern;
     use crate::pattern::PatternType::Stripe;
     use crate::ray::Ray;
@@ -145,7 +145,7 @@ mod test {
             TypedVec::vector(0.0, 0.0, 1.0),
         );
         let mut s = Sphere::new();
-        s.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        s.set_transform(Matrix4::scaling(2.0, 2.0, 2.0));
         let xs = s.intersect(r);
         assert_eq!(xs.len(), 2);
         assert_eq!(xs[0].t, 3.0);
@@ -159,7 +159,7 @@ mod test {
             TypedVec::vector(0.0, 0.0, 1.0),
         );
         let mut s = Sphere::new();
-        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        s.set_transform(Matrix4::translation(5.0, 0.0, 0.0));
         let xs = s.intersect(r);
         assert_eq!(xs.len(), 0);
     }
@@ -200,9 +200,10 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_normal_translated() {
         let mut s = Sphere::new();
-        s.set_transform(Matrix::translation(0f64, 1f64, 0f64));
+        s.set_transform(Matrix4::translation(0f64, 1f64, 0f64));
         let n = s
             .normal_at(TypedVec::point(0.0, 1.70711, -0.70711))
             .unwrap();
@@ -215,8 +216,8 @@ mod test {
     #[test]
     fn test_normal_transformed() {
         let mut s = Sphere::new();
-        let m = Matrix::scaling(1f64, 0.5, 1f64)
-            * Matrix::rotation(Axis::Z, std::f64::consts::PI / 5f64);
+        let m = Matrix4::scaling(1f64, 0.5, 1f64)
+            * Matrix4::rotation(Axis::Z, std::f64::consts::PI / 5f64);
         s.set_transform(m);
         let v = 2f64.sqrt() / 2.0;
         let p = TypedVec::point(0f64, v, -v);
@@ -230,7 +231,7 @@ mod test {
     #[test]
     fn test_stripe_object_transform() {
         let mut s = Sphere::new();
-        s.transform = Some(Matrix::scaling(2f64, 2f64, 2f64));
+        s.transform = Some(Matrix4::scaling(2f64, 2f64, 2f64).into());
         let p = Pattern::new(Stripe, *WHITE, *BLACK, false);
         let c = s.pattern_at(&p, TypedVec::point(1.5, 0f64, 0f64)).unwrap();
         assert_eq!(c, *WHITE)
@@ -240,7 +241,7 @@ mod test {
     fn test_stripe_pattern_transform() {
         let s = Sphere::new();
         let mut p = Pattern::new(Stripe, *WHITE, *BLACK, false);
-        p.transform = Some(Matrix::scaling(2f64, 2f64, 2f64));
+        p.transform = Some(Matrix4::scaling(2f64, 2f64, 2f64).into());
         let c = s.pattern_at(&p, TypedVec::point(1.5, 0f64, 0f64)).unwrap();
         assert_eq!(c, *WHITE)
     }
@@ -248,10 +249,22 @@ mod test {
     #[test]
     fn test_stripe_object_pattern_transform() {
         let mut s = Sphere::new();
-        s.transform = Some(Matrix::scaling(2f64, 2f64, 2f64));
+        s.transform = Some(Matrix4::scaling(2f64, 2f64, 2f64).into());
         let mut p = Pattern::new(Stripe, *WHITE, *BLACK, false);
-        p.transform = Some(Matrix::translation(0.5f64, 0f64, 0f64));
+        p.transform = Some(Matrix4::translation(0.5f64, 0f64, 0f64).into());
         let c = s.pattern_at(&p, TypedVec::point(2.5, 0f64, 0f64)).unwrap();
         assert_eq!(c, *WHITE)
     }
+
+    #[test]
+    fn test_bounds() {
+        let s = Sphere::new();
+        assert_eq!(
+            s.bounds(),
+            BoundingBox::new(
+                TypedVec::point(-1f64, -1f64, -1f64),
+                TypedVec::point(1f64, 1f64, 1f64)
+            )
+        );
+    }
 }
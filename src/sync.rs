@@ -0,0 +1,76 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A `RwLock` that still clones and compares by value.
+#[derive(Default)]
+pub struct Lock<T>(RwLock<T>);
+
+impl<T> Lock<T> {
+    pub fn new(value: T) -> Self {
+        Self(RwLock::new(value))
+    }
+
+    // A panic while holding the lock can't leave a half-written value behind
+    // for anything stored here, so poisoning is ignored.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn replace(&self, value: T) -> T {
+        std::mem::replace(&mut *self.write(), value)
+    }
+}
+
+impl<T: Copy> Lock<T> {
+    pub fn get(&self) -> T {
+        *self.read()
+    }
+}
+
+impl<T: Clone> Clone for Lock<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.read().clone())
+    }
+}
+
+impl<T: PartialEq> PartialEq for Lock<T> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other) || *self.read() == *other.read()
+    }
+}
+
+impl<T: PartialOrd> PartialOrd for Lock<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if std::ptr::eq(self, other) {
+            return Some(Ordering::Equal);
+        }
+        self.read().partial_cmp(&*other.read())
+    }
+}
+
+impl<T: Debug> Debug for Lock<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.read().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sync::Lock;
+
+    #[test]
+    fn test_lock() {
+        let l = Lock::new(vec![1, 2]);
+        l.write().push(3);
+        assert_eq!(*l.read(), vec![1, 2, 3]);
+        assert_eq!(l.replace(vec![]), vec![1, 2, 3]);
+        assert_eq!(l.clone(), Lock::new(vec![]));
+        assert_eq!(Lock::new(4).get(), 4);
+        assert!(Lock::new(1) < Lock::new(2));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::colour::Colour;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub enum ToneMap {
+    #[default]
+    Clamp,
+    /// `c / (1 + c)`.
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
+
+impl ToneMap {
+    pub fn map(&self, c: f64) -> f64 {
+        let c = c.max(0f64);
+        match self {
+            ToneMap::Clamp => c.min(1f64),
+            ToneMap::Reinhard => c / (1f64 + c),
+            ToneMap::Aces => {
+                ((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14)).clamp(0f64, 1f64)
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub enum Transfer {
+    #[default]
+    Linear,
+    Srgb,
+    Gamma(f64),
+}
+
+impl Transfer {
+    pub fn encode(&self, c: f64) -> f64 {
+        match self {
+            Transfer::Linear => c,
+            Transfer::Srgb => {
+                if c <= 0.0031308 {
+                    12.92 * c
+                } else {
+                    1.055 * c.powf(1f64 / 2.4) - 0.055
+                }
+            }
+            Transfer::Gamma(gamma) => c.powf(1f64 / gamma),
+        }
+    }
+}
+
+/// Exposure, then tone mapping, then the transfer curve.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct PostProcess {
+    /// In stops.
+    pub exposure: f64,
+    pub tone_map: ToneMap,
+    pub transfer: Transfer,
+}
+
+impl PostProcess {
+    pub fn new(exposure: f64, tone_map: ToneMap, transfer: Transfer) -> Self {
+        Self {
+            exposure,
+            tone_map,
+            transfer,
+        }
+    }
+
+    pub fn apply(&self, colour: Colour) -> Colour {
+        let scale = 2f64.powf(self.exposure);
+        let channel = |c: f64| self.transfer.encode(self.tone_map.map(c * scale));
+        Colour::new(
+            channel(colour.red),
+            channel(colour.green),
+            channel(colour.blue),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::colour::Colour;
+    use crate::tonemap::{PostProcess, ToneMap, Transfer};
+
+    #[test]
+    fn test_tone_map() {
+        let examples = vec![
+            ("clamp", ToneMap::Clamp, [0.0, 0.0, 0.5, 1.0, 1.0]),
+            ("reinhard", ToneMap::Reinhard, [0.0, 0.0, 0.33333, 0.5, 0.8]),
+            ("aces", ToneMap::Aces, [0.0, 0.0, 0.61631, 0.8038, 0.97342]),
+        ];
+        for (name, tone_map, expected) in examples {
+            dbg!(&name);
+            for (c, e) in [-1.0, 0.0, 0.5, 1.0, 4.0].iter().zip(expected.iter()) {
+                assert_eq!(crate::roundf(tone_map.map(*c), 100000f64), *e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_transfer() {
+        let examples = vec![
+            ("linear", Transfer::Linear, [0.0, 0.002, 0.5, 1.0]),
+            ("srgb", Transfer::Srgb, [0.0, 0.02584, 0.73536, 1.0]),
+            ("gamma", Transfer::Gamma(2.2), [0.0, 0.05932, 0.72974, 1.0]),
+        ];
+        for (name, transfer, expected) in examples {
+            dbg!(&name);
+            for (c, e) in [0.0, 0.002, 0.5, 1.0].iter().zip(expected.iter()) {
+                assert_eq!(crate::roundf(transfer.encode(*c), 100000f64), *e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_post_process() {
+        let colour = Colour::new(0.25, 1.0, 3.0);
+        assert_eq!(
+            PostProcess::default().apply(colour),
+            Colour::new(0.25, 1.0, 1.0)
+        );
+        let p = PostProcess::new(1.0, ToneMap::Reinhard, Transfer::Linear);
+        assert_eq!(
+            p.apply(colour).round(100000f64),
+            Colour::new(0.33333, 0.66667, 0.85714)
+        );
+        let p = PostProcess::new(-1.0, ToneMap::Clamp, Transfer::Srgb);
+        assert_eq!(
+            p.apply(colour).round(100000f64),
+            Colour::new(0.38857, 0.73536, 1.0)
+        );
+    }
+}
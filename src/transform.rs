@@ -0,0 +1,85 @@
+use crate::matrix4::Matrix4;
+use anyhow::{anyhow, Result};
+
+/// A transform with its inverse and inverse-transpose worked out once.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Transform {
+    matrix: Matrix4,
+    // `None` when the matrix can't be inverted, which is only an error once
+    // something needs the inverse.
+    inverse: Option<Matrix4>,
+    inverse_transpose: Option<Matrix4>,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix4) -> Self {
+        let inverse = matrix.inverse().ok();
+        let inverse_transpose = inverse.as_ref().map(|i| i.transpose());
+        Self {
+            matrix,
+            inverse,
+            inverse_transpose,
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> Result<&Matrix4> {
+        self.inverse
+            .as_ref()
+            .ok_or_else(|| anyhow!("transform can't be inverted"))
+    }
+
+    pub fn inverse_transpose(&self) -> Result<&Matrix4> {
+        self.inverse_transpose
+            .as_ref()
+            .ok_or_else(|| anyhow!("transform can't be inverted"))
+    }
+}
+
+impl From<Matrix4> for Transform {
+    fn from(matrix: Matrix4) -> Self {
+        Self::new(matrix)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matrix::Axis;
+    use crate::matrix4::Matrix4;
+    use crate::transform::Transform;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_cached_inverse() {
+        let examples = vec![
+            ("identity", Matrix4::identity()),
+            ("translation", Matrix4::translation(5f64, -3f64, 2f64)),
+            (
+                "chained",
+                Matrix4::translation(1f64, 2f64, 3f64)
+                    * Matrix4::rotation(Axis::Y, PI / 3f64)
+                    * Matrix4::scaling(2f64, 1f64, 0.5),
+            ),
+        ];
+        for (name, m) in examples {
+            dbg!(&name);
+            let t = Transform::from(m);
+            assert_eq!(t.matrix(), &m);
+            assert_eq!(t.inverse().unwrap(), &m.inverse().unwrap());
+            assert_eq!(
+                t.inverse_transpose().unwrap(),
+                &m.inverse().unwrap().transpose()
+            );
+        }
+    }
+
+    #[test]
+    fn test_singular() {
+        let t = Transform::new(Matrix4::scaling(0f64, 1f64, 1f64));
+        assert!(t.inverse().is_err());
+        assert!(t.inverse_transpose().is_err());
+    }
+}
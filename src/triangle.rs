@@ -0,0 +1,171 @@
+use crate::bounds::BoundingBox;
+use crate::hittable::HittableImpl;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::TypedVec;
+use crate::{shape, EPSILON};
+use anyhow::Result;
+
+shape!(
+    Triangle,
+    nodefault,
+    p1 -> TypedVec,
+    p2 -> TypedVec,
+    p3 -> TypedVec,
+    e1 -> TypedVec,
+    e2 -> TypedVec,
+    normal -> TypedVec
+);
+
+impl<'a> Triangle<'a> {
+    pub fn new(p1: TypedVec, p2: TypedVec, p3: TypedVec) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross_product(e1).normalize(),
+            material: Material::default(),
+            parent: None,
+            transform: None,
+            motion: None,
+        }
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
+        match moller_trumbore(self.p1, self.e1, self.e2, ray) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, self, u, v)],
+            None => vec![],
+        }
+    }
+
+    fn local_normal_at(&self, _: TypedVec) -> Result<TypedVec> {
+        Ok(self.normal)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        bounds.add_point(self.p1);
+        bounds.add_point(self.p2);
+        bounds.add_point(self.p3);
+        bounds
+    }
+}
+
+/// Möller–Trumbore: the distance along the ray and the hit's `u` and `v`.
+pub(crate) fn moller_trumbore(
+    p1: TypedVec,
+    e1: TypedVec,
+    e2: TypedVec,
+    ray: Ray,
+) -> Option<(f64, f64, f64)> {
+    let dir_cross_e2 = ray.direction.cross_product(e2);
+    let det = e1.dot_product(dir_cross_e2);
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1f64 / det;
+    let p1_to_origin = ray.origin - p1;
+    let u = f * p1_to_origin.dot_product(dir_cross_e2);
+    if !(0f64..=1f64).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross_product(e1);
+    let v = f * ray.direction.dot_product(origin_cross_e1);
+    if v < 0f64 || (u + v) > 1f64 {
+        return None;
+    }
+
+    Some((f * e2.dot_product(origin_cross_e1), u, v))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hittable::{Hittable, HittableImpl};
+    use crate::ray::Ray;
+    use crate::triangle::Triangle;
+    use crate::vec3::TypedVec;
+
+    fn triangle<'a>() -> Triangle<'a> {
+        Triangle::new(
+            TypedVec::point(0f64, 1f64, 0f64),
+            TypedVec::point(-1f64, 0f64, 0f64),
+            TypedVec::point(1f64, 0f64, 0f64),
+        )
+    }
+
+    #[test]
+    fn test_construct() {
+        let t = triangle();
+        assert_eq!(t.e1, TypedVec::vector(-1f64, -1f64, 0f64));
+        assert_eq!(t.e2, TypedVec::vector(1f64, -1f64, 0f64));
+        assert_eq!(t.normal, TypedVec::vector(0f64, 0f64, -1f64));
+    }
+
+    #[test]
+    fn test_normal() {
+        let t = triangle();
+        for p in [
+            TypedVec::point(0f64, 0.5, 0f64),
+            TypedVec::point(-0.5, 0.75, 0f64),
+            TypedVec::point(0.5, 0.25, 0f64),
+        ] {
+            assert_eq!(t.normal_at(p).unwrap(), t.normal);
+        }
+    }
+
+    #[test]
+    fn test_parallel_ray() {
+        let t = triangle();
+        let r = Ray::new(
+            TypedVec::point(0f64, -1f64, -2f64),
+            TypedVec::vector(0f64, 1f64, 0f64),
+        );
+        assert!(t.intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_ray_misses_edges() {
+        let t = triangle();
+        let examples = vec![
+            ("p1-p3", TypedVec::point(1f64, 1f64, -2f64)),
+            ("p1-p2", TypedVec::point(-1f64, 1f64, -2f64)),
+            ("p2-p3", TypedVec::point(0f64, -1f64, -2f64)),
+        ];
+        for (name, origin) in examples {
+            dbg!(&name);
+            let r = Ray::new(origin, TypedVec::vector(0f64, 0f64, 1f64));
+            assert!(t.intersect(r).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_ray_strikes() {
+        let t = triangle();
+        let r = Ray::new(
+            TypedVec::point(0f64, 0.5, -2f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let xs = t.intersect(r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 2f64);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let t = Triangle::new(
+            TypedVec::point(-3f64, 7f64, 2f64),
+            TypedVec::point(6f64, 2f64, -4f64),
+            TypedVec::point(2f64, -1f64, -1f64),
+        );
+        let b = t.bounds();
+        assert_eq!(b.min, TypedVec::point(-3f64, -1f64, -4f64));
+        assert_eq!(b.max, TypedVec::point(6f64, 7f64, 2f64));
+    }
+}
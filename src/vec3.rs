@@ -20,13 +20,7 @@ pub struct TypedVec {
 impl std::fmt::Display for TypedVec {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         fn clamp(val: f64) -> f64 {
-            if val < 0f64 {
-                0.0
-            } else if val > 1f64 {
-                1.0
-            } else {
-                val
-            }
+            val.clamp(0.0, 1.0)
         }
 
         write!(
@@ -243,7 +237,7 @@ mod tests {
             w: 1f64,
             is: VecType::Point,
         };
-        assert_eq!(t.is_point(), true)
+        assert!(t.is_point())
     }
 
     #[test]
@@ -255,7 +249,7 @@ mod tests {
             w: 0f64,
             is: VecType::Vector,
         };
-        assert_eq!(t.is_vector(), true)
+        assert!(t.is_vector())
     }
 
     #[test]
@@ -298,7 +292,7 @@ mod tests {
                 is: VecType::Point,
             }
         );
-        assert_eq!(t.is_point(), true)
+        assert!(t.is_point())
     }
 
     #[test]
@@ -314,7 +308,7 @@ mod tests {
                 is: VecType::Vector,
             }
         );
-        assert_eq!(t.is_vector(), true)
+        assert!(t.is_vector())
     }
 
     #[test]
@@ -330,7 +324,7 @@ mod tests {
                 is: VecType::Vector,
             }
         );
-        assert_eq!(t.is_vector(), true)
+        assert!(t.is_vector())
     }
 
     #[test]
@@ -381,11 +375,8 @@ mod tests {
     fn test_cross_product() {
         let v = TypedVec::vector(1.0, 2.0, 3.0);
         let r = TypedVec::vector(2.0, 3.0, 4.0);
-        assert_eq!(
-            v.cross_product(r.clone()),
-            TypedVec::vector(-1.0, 2.0, -1.0)
-        );
-        assert_eq!(r.cross_product(v.clone()), TypedVec::vector(1.0, -2.0, 1.0));
+        assert_eq!(v.cross_product(r), TypedVec::vector(-1.0, 2.0, -1.0));
+        assert_eq!(r.cross_product(v), TypedVec::vector(1.0, -2.0, 1.0));
     }
 
     #[test]
@@ -1,53 +1,77 @@
+use crate::bvh::{Bvh, BvhStats};
 use crate::colour::{Colour, BLACK};
 use crate::hittable::Hittable;
 use crate::intersection::{Intersection, Intersections, PreComp};
-use crate::lighting::Point;
+use crate::lighting::{Light, LightSample, Point};
 use crate::ray::Ray;
 use crate::vec3::TypedVec;
 use std::fmt::Debug;
 
 #[derive(Clone, Debug, PartialOrd, PartialEq)]
 pub struct World<'a> {
-    light: Point,
+    pub lights: Vec<Light>,
     pub objects: Vec<&'a dyn Hittable>,
+    bvh: Option<Bvh<'a>>,
 }
 
 impl<'a> Default for World<'a> {
     fn default() -> Self {
         Self {
-            light: Point::new(
+            lights: vec![Point::new(
                 TypedVec::point(-10f64, 10f64, -10f64),
                 Colour::new(1f64, 1f64, 1f64),
-            ),
+            )
+            .into()],
             objects: Vec::new(),
+            bvh: None,
         }
     }
 }
 
 impl<'a> World<'a> {
-    pub fn new(light: Point) -> Self {
+    pub fn new<L: Into<Light>>(light: L) -> Self {
         World {
-            light,
+            lights: vec![light.into()],
             objects: Vec::new(),
+            bvh: None,
         }
     }
 
-    fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+    /// A snapshot of `objects`; build it again after changing them.
+    pub fn build_bvh(&mut self) -> BvhStats {
+        let bvh = Bvh::new(&self.objects);
+        let stats = bvh.stats();
+        self.bvh = Some(bvh);
+        stats
+    }
+
+    fn intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
+        if let Some(bvh) = &self.bvh {
+            return bvh.intersect(ray);
+        }
         let mut r: Vec<Intersection> = self.objects.iter().flat_map(|o| o.intersect(ray)).collect();
         r.sort_by(|a, b| a.partial_cmp(b).unwrap());
         r
     }
 
+    pub fn add_light<L: Into<Light>>(&mut self, light: L) {
+        self.lights.push(light.into());
+    }
+
     fn shade_hit(&self, comps: PreComp, remaining: usize) -> Colour {
-        let shadowed = self.is_shadowed(comps.over_point);
-        let surface = comps.obj.material().lighting(
-            comps.obj,
-            self.light,
-            comps.over_point,
-            comps.eyev,
-            comps.normalv,
-            shadowed,
-        );
+        let material = comps.obj.material();
+        let colour = material.surface_at(comps.obj, comps.over_point, comps.time);
+        let surface = self.lights.iter().fold(*BLACK, |acc, light| {
+            let shadowed = self.is_shadowed_at(light, comps.over_point, comps.time);
+            acc + material.shade(
+                colour,
+                *light,
+                comps.over_point,
+                comps.eyev,
+                comps.normalv,
+                shadowed,
+            )
+        });
         let reflected = self.reflected_colour(comps.clone(), remaining);
         let refracted = self.refracted_colour(comps.clone(), remaining);
         let m = comps.obj.material();
@@ -59,13 +83,26 @@ impl<'a> World<'a> {
         }
     }
 
-    fn is_shadowed(&self, point: TypedVec) -> bool {
-        let v = self.light.position - point;
-        let distance = v.magnitude();
-        let toward = v.normalize();
-        let r = Ray::new(point, toward);
+    #[cfg(test)]
+    fn is_shadowed(&self, light: &Light, point: TypedVec) -> f64 {
+        self.is_shadowed_at(light, point, 0f64)
+    }
+
+    fn is_shadowed_at(&self, light: &Light, point: TypedVec, time: f64) -> f64 {
+        let samples = light.samples(point);
+        let blocked = samples
+            .iter()
+            .filter(|sample| self.is_occluded(sample, point, time))
+            .count();
+        blocked as f64 / samples.len() as f64
+    }
+
+    // Directional lights have an infinite distance, so anything in the way
+    // blocks them.
+    fn is_occluded(&self, sample: &LightSample, point: TypedVec, time: f64) -> bool {
+        let r = Ray::new(point, sample.direction).with_time(time);
         if let Some(hit) = Intersections::from_iter(self.intersect(r)).hit() {
-            if hit.t < distance {
+            if hit.t < sample.distance {
                 return true;
             }
         }
@@ -84,7 +121,7 @@ impl<'a> World<'a> {
         if remaining < 1 || comps.obj.material().reflective == 0f64 {
             return *BLACK;
         }
-        let r = Ray::new(comps.over_point, comps.reflectv);
+        let r = Ray::new(comps.over_point, comps.reflectv).with_time(comps.time);
         let colour = self.colour_at(r, remaining - 1);
         colour * comps.obj.material().reflective
     }
@@ -103,7 +140,7 @@ impl<'a> World<'a> {
 
         let cos_t = (1.0 - sin2_t).sqrt();
         let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
-        let refract = Ray::new(comps.under_point, direction);
+        let refract = Ray::new(comps.under_point, direction).with_time(comps.time);
         self.colour_at(refract, remaining - 1) * comps.obj.material().transparency
     }
 }
@@ -111,24 +148,27 @@ impl<'a> World<'a> {
 #[cfg(test)]
 pub mod test {
     use crate::colour::{Colour, BLACK, WHITE};
+    use crate::group;
+    use crate::group::{Group, Groupable};
     use crate::intersection::{Intersection, Intersections};
     use crate::lighting;
-    use crate::lighting::Point;
+    use crate::lighting::{AreaLight, DirectionalLight, Light, Point};
     use crate::material::Material;
-    use crate::matrix::Matrix;
+    use crate::matrix4::Matrix4;
     use crate::pattern::Pattern;
     use crate::plane::Plane;
     use crate::ray::Ray;
     use crate::sphere::Sphere;
     use crate::vec3::TypedVec;
     use crate::world::World;
+    use std::sync::Arc;
 
     #[macro_export]
     macro_rules! default_world {
         ($v:ident,$s:ident,$x:ident) => {
-            use crate::material::Material;
-            use crate::sphere::Sphere;
-            use crate::world::World;
+            use $crate::material::Material;
+            use $crate::sphere::Sphere;
+            use $crate::world::World;
             let $s = Sphere {
                 material: Material {
                     colour: Colour::new(0.8, 1.0, 0.6),
@@ -139,7 +179,7 @@ pub mod test {
                 ..Default::default()
             };
             let $x = Sphere {
-                transform: Some(Matrix::scaling(0.5, 0.5, 0.5)),
+                transform: Some(Matrix4::scaling(0.5, 0.5, 0.5).into()),
                 ..Default::default()
             };
 
@@ -162,10 +202,44 @@ pub mod test {
         assert_eq!(xs[3].t, 6f64);
     }
 
+    #[test]
+    fn test_world_intersect_bvh() {
+        default_world!(w, s1, s2);
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let expected: Vec<f64> = w.intersect(r).iter().map(|i| i.t).collect();
+        let colour = w.colour_at(r, 5);
+        let stats = w.build_bvh();
+        assert_eq!(stats.nodes, 1);
+        assert_eq!(stats.depth, 1);
+        let xs: Vec<f64> = w.intersect(r).iter().map(|i| i.t).collect();
+        assert_eq!(xs, expected);
+        assert_eq!(w.colour_at(r, 5), colour);
+    }
+
+    #[test]
+    fn test_world_intersect_group() {
+        let g = Arc::new(Group::new());
+        let mut s = Sphere::default();
+        group!(g, s);
+        let mut w = World::default();
+        w.objects.push(&*g);
+        let bare = Sphere::default();
+        let mut expected = World::default();
+        expected.objects.push(&bare);
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        assert_eq!(w.colour_at(r, 4), expected.colour_at(r, 4));
+    }
+
     #[test]
     fn test_shading_inside() {
         default_world!(w, s1, s2);
-        w.light = lighting::Point::new(TypedVec::point(0f64, 0.25, 0f64), *WHITE);
+        w.lights = vec![lighting::Point::new(TypedVec::point(0f64, 0.25, 0f64), *WHITE).into()];
         let r = Ray::new(
             TypedVec::point(0f64, 0f64, -0f64),
             TypedVec::vector(0f64, 0f64, 1f64),
@@ -229,7 +303,7 @@ pub mod test {
             ..Default::default()
         };
         let s2 = Sphere {
-            transform: Some(Matrix::scaling(0.5, 0.5, 0.5)),
+            transform: Some(Matrix4::scaling(0.5, 0.5, 0.5).into()),
             material: Material {
                 ambient: 1f64,
                 ..Default::default()
@@ -253,36 +327,79 @@ pub mod test {
     fn test_no_shadow() {
         default_world!(w, s1, s2);
         let p = TypedVec::point(0f64, 10f64, 0f64);
-        assert!(!w.is_shadowed(p))
+        assert_eq!(w.is_shadowed(&w.lights[0], p), 0f64)
     }
 
     #[test]
     fn test_shadow_obj_between_light_and_point() {
         default_world!(w, s1, s2);
         let p = TypedVec::point(10f64, -10f64, 10f64);
-        assert!(w.is_shadowed(p))
+        assert_eq!(w.is_shadowed(&w.lights[0], p), 1f64)
     }
 
     #[test]
     fn test_light_between_point_and_obj() {
         default_world!(w, s1, s2);
         let p = TypedVec::point(-20f64, 20f64, -20f64);
-        assert!(!w.is_shadowed(p))
+        assert_eq!(w.is_shadowed(&w.lights[0], p), 0f64)
     }
 
     #[test]
     fn test_shadow_object_behind_point() {
         default_world!(w, s1, s2);
         let p = TypedVec::point(-2f64, 2f64, -2f64);
-        assert!(!w.is_shadowed(p))
+        assert_eq!(w.is_shadowed(&w.lights[0], p), 0f64)
+    }
+
+    #[test]
+    fn test_area_light_shadow_fraction() {
+        default_world!(w, s1, s2);
+        let l: Light = AreaLight::new(
+            TypedVec::point(-0.5, -0.5, -5f64),
+            TypedVec::vector(1f64, 0f64, 0f64),
+            2,
+            TypedVec::vector(0f64, 1f64, 0f64),
+            2,
+            *WHITE,
+        )
+        .into();
+        let examples = vec![
+            (TypedVec::point(0f64, 0f64, 2f64), 1f64),
+            (TypedVec::point(1f64, -1f64, 2f64), 0.75),
+            (TypedVec::point(1.5, 0f64, 2f64), 0.5),
+            (TypedVec::point(1.25, 1.25, 3f64), 0.25),
+            (TypedVec::point(0f64, 0f64, -2f64), 0f64),
+        ];
+        for (p, expected) in examples {
+            dbg!(&p);
+            assert_eq!(w.is_shadowed(&l, p), expected);
+        }
+    }
+
+    #[test]
+    fn test_directional_light_shadow() {
+        default_world!(w, s1, s2);
+        let l: Light = DirectionalLight::new(TypedVec::vector(0f64, -1f64, 0f64), *WHITE).into();
+        let examples = vec![
+            ("above", TypedVec::point(0f64, 10f64, 0f64), 0f64),
+            ("below", TypedVec::point(0f64, -10f64, 0f64), 1f64),
+            ("far below", TypedVec::point(0f64, -1000f64, 0f64), 1f64),
+            ("beside", TypedVec::point(5f64, -10f64, 0f64), 0f64),
+        ];
+        for (name, p, expected) in examples {
+            dbg!(&name);
+            assert_eq!(w.is_shadowed(&l, p), expected);
+        }
     }
 
     #[test]
     fn test_shade_hit_in_shadow() {
         let mut w = World::new(Point::new(TypedVec::point(0f64, 0f64, -10f64), *WHITE));
         let s1 = Sphere::default();
-        let mut s2 = Sphere::default();
-        s2.transform = Some(Matrix::translation(0f64, 0f64, 10f64));
+        let s2 = Sphere {
+            transform: Some(Matrix4::translation(0f64, 0f64, 10f64).into()),
+            ..Default::default()
+        };
         w.objects = vec![&s1, &s2];
         let r = Ray::new(
             TypedVec::point(0f64, 0f64, 5f64),
@@ -294,12 +411,53 @@ pub mod test {
         assert_eq!(w.shade_hit(comps, 4), Colour::new(0.1, 0.1, 0.1));
     }
 
+    #[test]
+    fn test_shade_hit_two_lights() {
+        default_world!(w, s1, s2);
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, -5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let i = Intersection::new(4f64, w.objects[0]);
+        let xs = Intersections::from_iter(vec![i.clone()]);
+        let single = w.shade_hit(i.precompute(r, &xs), 4);
+        w.add_light(w.lights[0]);
+        let double = w.shade_hit(i.precompute(r, &xs), 4);
+        assert_eq!(double.round(100000f64), (single * 2f64).round(100000f64));
+    }
+
+    #[test]
+    fn test_shade_hit_shadow_per_light() {
+        let mut w = World::new(Point::new(TypedVec::point(0f64, 0f64, -10f64), *WHITE));
+        w.add_light(Point::new(TypedVec::point(0f64, 0f64, 5f64), *WHITE));
+        let s1 = Sphere::default();
+        let s2 = Sphere {
+            transform: Some(Matrix4::translation(0f64, 0f64, 10f64).into()),
+            ..Default::default()
+        };
+        w.objects = vec![&s1, &s2];
+        let r = Ray::new(
+            TypedVec::point(0f64, 0f64, 5f64),
+            TypedVec::vector(0f64, 0f64, 1f64),
+        );
+        let i = Intersection::new(4f64, &s2);
+        let xs = Intersections::from_iter(vec![i.clone()]);
+        let comps = i.precompute(r, &xs);
+        // Ambient only from the blocked light, full shading from the other.
+        assert_eq!(
+            w.shade_hit(comps, 4).round(100000f64),
+            Colour::new(2f64, 2f64, 2f64)
+        );
+    }
+
     #[test]
     fn test_nonreflective_colour() {
         let mut w = World::new(Point::new(TypedVec::point(0f64, 0f64, -10f64), *WHITE));
         let s1 = Sphere::default();
-        let mut s2 = Sphere::default();
-        s2.transform = Some(Matrix::scaling(0.5, 0.5, 0.5));
+        let mut s2 = Sphere {
+            transform: Some(Matrix4::scaling(0.5, 0.5, 0.5).into()),
+            ..Default::default()
+        };
         s2.material.ambient = 1f64;
         w.objects = vec![&s1, &s2];
         let r = Ray::new(
@@ -317,7 +475,7 @@ pub mod test {
         default_world!(w, s1, s2);
         let mut p = Plane::default();
         p.material.reflective = 0.5;
-        p.transform = Some(Matrix::translation(0f64, -1f64, 0f64));
+        p.transform = Some(Matrix4::translation(0f64, -1f64, 0f64).into());
         w.objects.push(&p);
 
         let r = Ray::new(
@@ -338,7 +496,7 @@ pub mod test {
         default_world!(w, s1, s2);
         let mut p = Plane::default();
         p.material.reflective = 0.5;
-        p.transform = Some(Matrix::translation(0f64, -1f64, 0f64));
+        p.transform = Some(Matrix4::translation(0f64, -1f64, 0f64).into());
         w.objects.push(&p);
 
         let r = Ray::new(
@@ -361,14 +519,14 @@ pub mod test {
         let lower = {
             let mut p = Plane::default();
             p.material.reflective = 1f64;
-            p.transform = Some(Matrix::translation(0f64, -1f64, 0f64));
+            p.transform = Some(Matrix4::translation(0f64, -1f64, 0f64).into());
             p
         };
 
         let upper = {
             let mut p = Plane::default();
             p.material.reflective = 1f64;
-            p.transform = Some(Matrix::translation(0f64, 1f64, 0f64));
+            p.transform = Some(Matrix4::translation(0f64, 1f64, 0f64).into());
             p
         };
 
@@ -380,7 +538,6 @@ pub mod test {
         );
 
         w.colour_at(r, 4);
-        assert!(true)
     }
 
     #[test]
@@ -388,7 +545,7 @@ pub mod test {
         default_world!(w, s1, s2);
         let mut p = Plane::default();
         p.material.reflective = 0.5;
-        p.transform = Some(Matrix::translation(0f64, -1f64, 0f64));
+        p.transform = Some(Matrix4::translation(0f64, -1f64, 0f64).into());
         w.objects.push(&p);
 
         let r = Ray::new(
@@ -438,7 +595,7 @@ pub mod test {
             ..Default::default()
         };
         let s2 = Sphere {
-            transform: Some(Matrix::scaling(0.5, 0.5, 0.5)),
+            transform: Some(Matrix4::scaling(0.5, 0.5, 0.5).into()),
             material: Material {
                 ambient: 1f64,
                 ..Default::default()
@@ -475,7 +632,7 @@ pub mod test {
             ..Default::default()
         };
         let s2 = Sphere {
-            transform: Some(Matrix::scaling(0.5, 0.5, 0.5)),
+            transform: Some(Matrix4::scaling(0.5, 0.5, 0.5).into()),
             material: Material {
                 ambient: 1f64,
                 ..Default::default()
@@ -514,7 +671,7 @@ pub mod test {
             ..Default::default()
         };
         let s2 = Sphere {
-            transform: Some(Matrix::scaling(0.5, 0.5, 0.5)),
+            transform: Some(Matrix4::scaling(0.5, 0.5, 0.5).into()),
             material: Material {
                 transparency: 1.0,
                 refractive_index: 1.5,
@@ -545,7 +702,7 @@ pub mod test {
     fn test_shade_trans() {
         default_world!(w, s1, s2);
         let floor = Plane {
-            transform: Some(Matrix::translation(0f64, -1f64, 0f64)),
+            transform: Some(Matrix4::translation(0f64, -1f64, 0f64).into()),
             material: Material {
                 transparency: 0.5,
                 refractive_index: 1.5,
@@ -554,7 +711,7 @@ pub mod test {
             ..Default::default()
         };
         let ball = Sphere {
-            transform: Some(Matrix::translation(0f64, -3.5f64, -0.5f64)),
+            transform: Some(Matrix4::translation(0f64, -3.5f64, -0.5f64).into()),
             material: Material {
                 colour: Colour::new(1f64, 0f64, 0f64),
                 ambient: 0.5,
@@ -583,7 +740,7 @@ pub mod test {
     fn test_shade_reflective_trans() {
         default_world!(w, s1, s2);
         let floor = Plane {
-            transform: Some(Matrix::translation(0f64, -1f64, 0f64)),
+            transform: Some(Matrix4::translation(0f64, -1f64, 0f64).into()),
             material: Material {
                 reflective: 0.5,
                 transparency: 0.5,
@@ -593,7 +750,7 @@ pub mod test {
             ..Default::default()
         };
         let ball = Sphere {
-            transform: Some(Matrix::translation(0f64, -3.5f64, -0.5f64)),
+            transform: Some(Matrix4::translation(0f64, -3.5f64, -0.5f64).into()),
             material: Material {
                 colour: Colour::new(1f64, 0f64, 0f64),
                 ambient: 0.5,